@@ -1,29 +1,449 @@
-use std::{collections::HashMap, io::Write};
+use std::{io::Write, rc::Rc};
 
-use crate::node::{Node, NodeKind, Program};
+use crate::{
+    node::{Function, Node, NodeKind, Obj, Program},
+    types::{align_to, Type},
+};
+
+const ARG_REGS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+const ARG_REGS8: [&str; 6] = ["%dil", "%sil", "%dl", "%cl", "%r8b", "%r9b"];
+const ARG_REGS16: [&str; 6] = ["%di", "%si", "%dx", "%cx", "%r8w", "%r9w"];
+const ARG_REGS32: [&str; 6] = ["%edi", "%esi", "%edx", "%ecx", "%r8d", "%r9d"];
+
+const FP_ARG_REGS: usize = 8; // 浮動小数点数の引数は %xmm0 から %xmm7 で渡す
+
+// 型の変換に使う命令。int より小さい型の値も %eax に int として保持しているので、
+// 変換元が char や short でも 32 ビットのレジスタから変換する。浮動小数点数は %xmm0 に置く
+const I32_I8: &str = "movsbl %al, %eax";
+const I32_U8: &str = "movzbl %al, %eax";
+const I32_I16: &str = "movswl %ax, %eax";
+const I32_U16: &str = "movzwl %ax, %eax";
+const I32_F32: &str = "cvtsi2ssl %eax, %xmm0";
+const I32_I64: &str = "movsxd %eax, %rax";
+const I32_F64: &str = "cvtsi2sdl %eax, %xmm0";
+
+const U32_F32: &str = "mov %eax, %eax; cvtsi2ssq %rax, %xmm0";
+const U32_I64: &str = "mov %eax, %eax";
+const U32_F64: &str = "mov %eax, %eax; cvtsi2sdq %rax, %xmm0";
+
+const I64_F32: &str = "cvtsi2ssq %rax, %xmm0";
+const I64_F64: &str = "cvtsi2sdq %rax, %xmm0";
+
+// 最上位ビットが立っている値は符号付きとしては負になるので、半分にしてから変換して2倍する
+const U64_F32: &str = "test %rax,%rax; js 1f; pxor %xmm0,%xmm0; cvtsi2ss %rax,%xmm0; jmp 2f; \
+    1: mov %rax,%rdi; and $1,%eax; pxor %xmm0,%xmm0; shr %rdi; \
+    or %rax,%rdi; cvtsi2ss %rdi,%xmm0; addss %xmm0,%xmm0; 2:";
+const U64_F64: &str = "test %rax,%rax; js 1f; pxor %xmm0,%xmm0; cvtsi2sd %rax,%xmm0; jmp 2f; \
+    1: mov %rax,%rdi; and $1,%eax; pxor %xmm0,%xmm0; shr %rdi; \
+    or %rax,%rdi; cvtsi2sd %rdi,%xmm0; addsd %xmm0,%xmm0; 2:";
+
+const F32_I8: &str = "cvttss2sil %xmm0, %eax; movsbl %al, %eax";
+const F32_U8: &str = "cvttss2sil %xmm0, %eax; movzbl %al, %eax";
+const F32_I16: &str = "cvttss2sil %xmm0, %eax; movswl %ax, %eax";
+const F32_U16: &str = "cvttss2sil %xmm0, %eax; movzwl %ax, %eax";
+const F32_I32: &str = "cvttss2sil %xmm0, %eax";
+const F32_U32: &str = "cvttss2siq %xmm0, %rax";
+const F32_I64: &str = "cvttss2siq %xmm0, %rax";
+const F32_U64: &str = "cvttss2siq %xmm0, %rax";
+const F32_F64: &str = "cvtss2sd %xmm0, %xmm0";
+
+const F64_I8: &str = "cvttsd2sil %xmm0, %eax; movsbl %al, %eax";
+const F64_U8: &str = "cvttsd2sil %xmm0, %eax; movzbl %al, %eax";
+const F64_I16: &str = "cvttsd2sil %xmm0, %eax; movswl %ax, %eax";
+const F64_U16: &str = "cvttsd2sil %xmm0, %eax; movzwl %ax, %eax";
+const F64_I32: &str = "cvttsd2sil %xmm0, %eax";
+const F64_U32: &str = "cvttsd2siq %xmm0, %rax";
+const F64_I64: &str = "cvttsd2siq %xmm0, %rax";
+const F64_U64: &str = "cvttsd2siq %xmm0, %rax";
+const F64_F32: &str = "cvtsd2ss %xmm0, %xmm0";
+
+// CAST_TABLE[変換元][変換先]。並びは type_id の順（i8, i16, i32, i64, u8, u16, u32, u64, f32, f64）
+#[rustfmt::skip]
+const CAST_TABLE: [[Option<&str>; 10]; 10] = [
+    [None,          None,           None,          Some(I32_I64), Some(I32_U8), Some(I32_U16), None,          Some(I32_I64), Some(I32_F32), Some(I32_F64)], // i8
+    [Some(I32_I8),  None,           None,          Some(I32_I64), Some(I32_U8), Some(I32_U16), None,          Some(I32_I64), Some(I32_F32), Some(I32_F64)], // i16
+    [Some(I32_I8),  Some(I32_I16),  None,          Some(I32_I64), Some(I32_U8), Some(I32_U16), None,          Some(I32_I64), Some(I32_F32), Some(I32_F64)], // i32
+    [Some(I32_I8),  Some(I32_I16),  None,          None,          Some(I32_U8), Some(I32_U16), None,          None,          Some(I64_F32), Some(I64_F64)], // i64
+    [Some(I32_I8),  None,           None,          Some(I32_I64), None,         None,          None,          Some(I32_I64), Some(I32_F32), Some(I32_F64)], // u8
+    [Some(I32_I8),  Some(I32_I16),  None,          Some(I32_I64), Some(I32_U8), None,          None,          Some(I32_I64), Some(I32_F32), Some(I32_F64)], // u16
+    [Some(I32_I8),  Some(I32_I16),  None,          Some(U32_I64), Some(I32_U8), Some(I32_U16), None,          Some(U32_I64), Some(U32_F32), Some(U32_F64)], // u32
+    [Some(I32_I8),  Some(I32_I16),  None,          None,          Some(I32_U8), Some(I32_U16), None,          None,          Some(U64_F32), Some(U64_F64)], // u64
+    [Some(F32_I8),  Some(F32_I16),  Some(F32_I32), Some(F32_I64), Some(F32_U8), Some(F32_U16), Some(F32_U32), Some(F32_U64), None,          Some(F32_F64)], // f32
+    [Some(F64_I8),  Some(F64_I16),  Some(F64_I32), Some(F64_I64), Some(F64_U8), Some(F64_U16), Some(F64_U32), Some(F64_U64), Some(F64_F32), None],          // f64
+];
+
+fn type_id(ty: &Type) -> usize {
+    match ty {
+        Type::Char => 0,
+        Type::Short => 1,
+        Type::Int | Type::Enum => 2,
+        Type::Long => 3,
+        Type::UChar => 4,
+        Type::UShort => 5,
+        Type::UInt => 6,
+        Type::Float => 8,
+        Type::Double => 9,
+        _ => 7,
+    }
+}
+
+/// System V ABI での値の分類。値を 8 バイトごとに区切り、それぞれを整数レジスタと
+/// XMM レジスタのどちらで受け渡すかを決める
+#[derive(Clone, Copy, PartialEq)]
+enum ArgClass {
+    Integer,
+    Sse,
+}
+
+/// 値を 8 バイトごとに分類する。16 バイトを超える構造体はメモリで受け渡すので None を返す
+fn classify(ty: &Type) -> Option<Vec<ArgClass>> {
+    if let Type::Struct(_) | Type::Union(_) = ty {
+        if ty.size() > 16 {
+            return None;
+        }
+
+        let classes = (0..align_to(ty.size(), 8) / 8)
+            .map(|i| {
+                if has_only_flonum(ty, i * 8, i * 8 + 8, 0) {
+                    ArgClass::Sse
+                } else {
+                    ArgClass::Integer
+                }
+            })
+            .collect();
+        return Some(classes);
+    }
+
+    if ty.is_flonum() {
+        Some(vec![ArgClass::Sse])
+    } else {
+        Some(vec![ArgClass::Integer])
+    }
+}
+
+/// `offset` にある `ty` 型の値のうち、[lo, hi) の範囲にあるものが全て浮動小数点数か
+fn has_only_flonum(ty: &Type, lo: i32, hi: i32, offset: i32) -> bool {
+    match ty {
+        Type::Struct(st) | Type::Union(st) => st
+            .borrow()
+            .members
+            .iter()
+            .all(|member| has_only_flonum(&member.ty, lo, hi, offset + member.offset)),
+        Type::Array(base, len) => {
+            (0..*len).all(|i| has_only_flonum(base, lo, hi, offset + base.size() * i))
+        }
+        _ => offset < lo || hi <= offset || ty.is_flonum(),
+    }
+}
+
+/// 引数ごとにスタックで渡すかどうかを返す。整数レジスタは6個、XMM レジスタは8個あり、
+/// 値の全体がレジスタに収まらなければスタックで渡す。`gp` は既に使った整数レジスタの数
+fn pass_on_stack<'a>(types: impl Iterator<Item = &'a Type>, mut gp: usize) -> Vec<bool> {
+    let mut fp = 0;
+    types
+        .map(|ty| {
+            let Some(classes) = classify(ty) else {
+                return true;
+            };
+            let sse = classes
+                .iter()
+                .filter(|&&class| class == ArgClass::Sse)
+                .count();
+            let integer = classes.len() - sse;
+            if gp + integer > ARG_REGS.len() || fp + sse > FP_ARG_REGS {
+                return true;
+            }
+            gp += integer;
+            fp += sse;
+            false
+        })
+        .collect()
+}
+
+/// スタックで渡すときに使う 8 バイトのスロットの数
+fn stack_slots(ty: &Type) -> i32 {
+    align_to(ty.size(), 8) / 8
+}
+
+/// 浮動小数点数の命令の接尾辞（float は ss、double は sd）
+fn fp_suffix(ty: &Type) -> &'static str {
+    match ty {
+        Type::Float => "ss",
+        _ => "sd",
+    }
+}
+
+/// 演算に使う %rax と %rdi の名前を返す。int 以下の整数は 32 ビットで計算する
+fn regs(ty: &Type) -> (&'static str, &'static str) {
+    if ty.is_integer() && ty.size() <= 4 {
+        ("%eax", "%edi")
+    } else {
+        ("%rax", "%rdi")
+    }
+}
 
 pub struct Generator {
-    locals: HashMap<String, i32>,
     stack_size: i32,
     count: i32,
+    depth: i32,
+    function_name: String,
+    break_labels: Vec<String>,
+    continue_labels: Vec<String>,
+    switches: Vec<i32>,          // 囲んでいる switch 文のラベル番号
+    ret_ptr_offset: Option<i32>, // 大きな構造体を返す関数で、戻り値の書き込み先を退避した位置
 }
 
 impl Generator {
     pub fn new() -> Self {
         Generator {
-            locals: HashMap::new(),
             stack_size: 0,
             count: 0,
+            depth: 0,
+            function_name: String::new(),
+            break_labels: vec![],
+            continue_labels: vec![],
+            switches: vec![],
+            ret_ptr_offset: None,
         }
     }
 
-    fn gen_address<W: Write>(&mut self, w: &mut W, node: &Node) {
-        if let NodeKind::Var(name) = &node.kind {
-            let _ = writeln!(w, "  lea {}(%rbp), %rax", self.locals.get(name).unwrap());
+    fn push<W: Write>(&mut self, w: &mut W) {
+        let _ = writeln!(w, "  push %rax");
+        self.depth += 1;
+    }
+
+    fn pop<W: Write>(&mut self, w: &mut W, reg: &str) {
+        let _ = writeln!(w, "  pop {reg}");
+        self.depth -= 1;
+    }
+
+    fn pushf<W: Write>(&mut self, w: &mut W) {
+        let _ = writeln!(w, "  sub $8, %rsp");
+        let _ = writeln!(w, "  movsd %xmm0, (%rsp)");
+        self.depth += 1;
+    }
+
+    fn popf<W: Write>(&mut self, w: &mut W, reg: usize) {
+        let _ = writeln!(w, "  movsd (%rsp), %xmm{reg}");
+        let _ = writeln!(w, "  add $8, %rsp");
+        self.depth -= 1;
+    }
+
+    /// %rax が指す構造体をスタックにコピーする
+    fn push_struct<W: Write>(&mut self, w: &mut W, ty: &Type) {
+        let size = align_to(ty.size(), 8);
+        let _ = writeln!(w, "  sub ${size}, %rsp");
+        for i in 0..ty.size() {
+            let _ = writeln!(w, "  mov {i}(%rax), %r8b");
+            let _ = writeln!(w, "  mov %r8b, {i}(%rsp)");
+        }
+        self.depth += size / 8;
+    }
+
+    /// 整数レジスタの下位 `size` バイトを `offset(%rbp)` に書き込む。
+    /// `regs` は同じレジスタの 64, 32, 16, 8 ビットの名前
+    fn store_gp<W: Write>(&mut self, w: &mut W, regs: [&str; 4], offset: i32, size: i32) {
+        match size {
+            8 => {
+                let _ = writeln!(w, "  mov {}, {offset}(%rbp)", regs[0]);
+            }
+            4 => {
+                let _ = writeln!(w, "  mov {}, {offset}(%rbp)", regs[1]);
+            }
+            2 => {
+                let _ = writeln!(w, "  mov {}, {offset}(%rbp)", regs[2]);
+            }
+            1 => {
+                let _ = writeln!(w, "  mov {}, {offset}(%rbp)", regs[3]);
+            }
+            _ => {
+                // 構造体の末尾の半端な大きさは1バイトずつ書き込む
+                for i in 0..size {
+                    let _ = writeln!(w, "  mov {}, {}(%rbp)", regs[3], offset + i);
+                    let _ = writeln!(w, "  shr $8, {}", regs[0]);
+                }
+            }
+        }
+    }
+
+    /// XMM レジスタの下位 `size` バイトを `offset(%rbp)` に書き込む
+    fn store_fp<W: Write>(&mut self, w: &mut W, reg: usize, offset: i32, size: i32) {
+        let insn = if size == 4 { "movss" } else { "movsd" };
+        let _ = writeln!(w, "  {insn} %xmm{reg}, {offset}(%rbp)");
+    }
+
+    /// 関数から返された構造体を、レジスタから `offset(%rbp)` の領域に書き込む
+    fn copy_ret_buffer<W: Write>(&mut self, w: &mut W, ty: &Type, offset: i32) {
+        let gp_regs = [
+            ["%rax", "%eax", "%ax", "%al"],
+            ["%rdx", "%edx", "%dx", "%dl"],
+        ];
+        let (mut gp, mut fp) = (0, 0);
+
+        for (i, class) in classify(ty).unwrap().into_iter().enumerate() {
+            let i = i as i32;
+            let size = (ty.size() - i * 8).min(8);
+            match class {
+                ArgClass::Sse => {
+                    self.store_fp(w, fp, offset + i * 8, size);
+                    fp += 1;
+                }
+                ArgClass::Integer => {
+                    self.store_gp(w, gp_regs[gp], offset + i * 8, size);
+                    gp += 1;
+                }
+            }
+        }
+
+        let _ = writeln!(w, "  lea {offset}(%rbp), %rax");
+    }
+
+    /// %rax が指す構造体を戻り値として返す。16 バイトまでならレジスタに読み込み、
+    /// それより大きければ呼び出し元が用意した領域にコピーする
+    fn copy_struct_return<W: Write>(&mut self, w: &mut W, ty: &Type) {
+        let Some(classes) = classify(ty) else {
+            let _ = writeln!(w, "  mov {}(%rbp), %rdi", self.ret_ptr_offset.unwrap());
+            for i in 0..ty.size() {
+                let _ = writeln!(w, "  mov {i}(%rax), %r8b");
+                let _ = writeln!(w, "  mov %r8b, {i}(%rdi)");
+            }
+            let _ = writeln!(w, "  mov %rdi, %rax");
+            return;
+        };
+
+        let gp_regs = [("%rax", "%al"), ("%rdx", "%dl")];
+        let (mut gp, mut fp) = (0, 0);
+        let _ = writeln!(w, "  mov %rax, %rdi");
+
+        for (i, class) in classes.into_iter().enumerate() {
+            let offset = i as i32 * 8;
+            let size = (ty.size() - offset).min(8);
+            match class {
+                ArgClass::Sse => {
+                    let insn = if size == 4 { "movss" } else { "movsd" };
+                    let _ = writeln!(w, "  {insn} {offset}(%rdi), %xmm{fp}");
+                    fp += 1;
+                }
+                ArgClass::Integer => {
+                    // 構造体の末尾を越えて読まないよう、半端な大きさは1バイトずつ読み込む
+                    let (reg, reg8) = gp_regs[gp];
+                    if size == 8 {
+                        let _ = writeln!(w, "  mov {offset}(%rdi), {reg}");
+                    } else {
+                        let _ = writeln!(w, "  mov $0, {reg}");
+                        for j in (0..size).rev() {
+                            let _ = writeln!(w, "  shl $8, {reg}");
+                            let _ = writeln!(w, "  mov {}(%rdi), {reg8}", offset + j);
+                        }
+                    }
+                    gp += 1;
+                }
+            }
+        }
+    }
+
+    /// %rax が指す値を %rax（浮動小数点数なら %xmm0）に読み込む
+    fn load<W: Write>(&mut self, w: &mut W, ty: &Type) {
+        // 配列や構造体はレジスタに読み込めないので、アドレスをそのまま値として扱う。
+        // 配列の場合はこれが先頭要素へのポインタへの変換になる
+        if let Type::Array(..) | Type::Struct(_) | Type::Union(_) = ty {
+            return;
+        }
+
+        if ty.is_flonum() {
+            let _ = writeln!(w, "  mov{} (%rax), %xmm0", fp_suffix(ty));
+            return;
+        }
+
+        // int より小さい値は int に符号拡張またはゼロ拡張して読み込む
+        let insn = match (ty.size(), ty.is_unsigned()) {
+            (1, false) => "movsbl (%rax), %eax",
+            (1, true) => "movzbl (%rax), %eax",
+            (2, false) => "movswl (%rax), %eax",
+            (2, true) => "movzwl (%rax), %eax",
+            (4, false) => "movsxd (%rax), %rax",
+            (4, true) => "mov (%rax), %eax",
+            _ => "mov (%rax), %rax",
+        };
+        let _ = writeln!(w, "  {insn}");
+    }
+
+    /// スタックトップが指すアドレスに %rax（浮動小数点数なら %xmm0）を書き込む
+    fn store<W: Write>(&mut self, w: &mut W, ty: &Type) {
+        self.pop(w, "%rdi");
+
+        if ty.is_flonum() {
+            let _ = writeln!(w, "  mov{} %xmm0, (%rdi)", fp_suffix(ty));
+            return;
+        }
+
+        // 構造体は1バイトずつコピーする
+        if let Type::Struct(_) | Type::Union(_) = ty {
+            for i in 0..ty.size() {
+                let _ = writeln!(w, "  mov {i}(%rax), %r8b");
+                let _ = writeln!(w, "  mov %r8b, {i}(%rdi)");
+            }
+            return;
+        }
+
+        let reg = match ty.size() {
+            1 => "%al",
+            2 => "%ax",
+            4 => "%eax",
+            _ => "%rax",
+        };
+        let _ = writeln!(w, "  mov {reg}, (%rdi)");
+    }
+
+    /// %rax（浮動小数点数なら %xmm0）の値が 0 かどうかを比較する
+    fn cmp_zero<W: Write>(&mut self, w: &mut W, ty: &Type) {
+        if ty.is_flonum() {
+            let _ = writeln!(w, "  xorps %xmm1, %xmm1");
+            let _ = writeln!(w, "  ucomi{} %xmm1, %xmm0", fp_suffix(ty));
+            return;
+        }
+
+        let (ax, _) = regs(ty);
+        let _ = writeln!(w, "  cmp $0, {ax}");
+    }
+
+    /// %rax の値を `from` 型から `to` 型に変換する
+    fn cast<W: Write>(&mut self, w: &mut W, from: &Type, to: &Type) {
+        if let Type::Void = to {
             return;
         }
 
-        panic!("ローカル変数ではありません: {node:?}");
+        if let Some(insn) = CAST_TABLE[type_id(from)][type_id(to)] {
+            let _ = writeln!(w, "  {insn}");
+        }
+    }
+
+    fn gen_address<W: Write>(&mut self, w: &mut W, node: &Node) {
+        match &node.kind {
+            NodeKind::Var(var) => {
+                if var.is_local {
+                    let _ = writeln!(w, "  lea {}(%rbp), %rax", var.offset.get());
+                } else {
+                    let _ = writeln!(w, "  lea {}(%rip), %rax", var.name);
+                }
+            }
+            NodeKind::Deref => {
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+            }
+            NodeKind::Member(member) => {
+                self.gen_address(w, node.lhs.as_ref().unwrap());
+                let _ = writeln!(w, "  add ${}, %rax", member.offset);
+            }
+            NodeKind::Comma => {
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                self.gen_address(w, node.rhs.as_ref().unwrap());
+            }
+            // 構造体を返す関数呼び出しの値は、戻り値を受け取った領域のアドレスになる
+            NodeKind::FunCall(_, _, Some(_)) => {
+                self.gen_expression(w, node);
+            }
+            _ => panic!("左辺値ではありません: {node:?}"),
+        }
     }
 
     fn gen_statement<W: Write>(&mut self, w: &mut W, node: &Node) {
@@ -34,126 +454,748 @@ impl Generator {
                 }
             }
             NodeKind::Return => {
-                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                if let Some(lhs) = node.lhs.as_ref() {
+                    self.gen_expression(w, lhs);
 
-                let _ = writeln!(w, "  jmp .L.return");
+                    let ty = lhs.ty.as_ref().unwrap();
+                    if let Type::Struct(_) | Type::Union(_) = ty {
+                        self.copy_struct_return(w, ty);
+                    }
+                }
+
+                let _ = writeln!(w, "  jmp .L.return.{}", self.function_name);
             }
             NodeKind::If(condition, then, els) => {
                 self.count += 1;
+                let c = self.count;
                 self.gen_expression(w, condition);
-                let _ = writeln!(w, "  cmp $0, %rax");
-                let _ = writeln!(w, "  je  .L.else.{}", self.count);
+                self.cmp_zero(w, condition.ty.as_ref().unwrap());
+                let _ = writeln!(w, "  je  .L.else.{c}");
                 self.gen_statement(w, then);
-                let _ = writeln!(w, "  jmp .L.end.{}", self.count);
-                let _ = writeln!(w, ".L.else.{}:", self.count);
+                let _ = writeln!(w, "  jmp .L.end.{c}");
+                let _ = writeln!(w, ".L.else.{c}:");
                 if let Some(els) = els.as_ref() {
                     self.gen_statement(w, els)
                 }
-                let _ = writeln!(w, ".L.end.{}:", self.count);
+                let _ = writeln!(w, ".L.end.{c}:");
+            }
+            NodeKind::While(condition, body) => {
+                self.count += 1;
+                let c = self.count;
+                let _ = writeln!(w, ".L.continue.{c}:");
+                self.gen_expression(w, condition);
+                self.cmp_zero(w, condition.ty.as_ref().unwrap());
+                let _ = writeln!(w, "  je  .L.break.{c}");
+                self.gen_loop_body(w, body, c);
+                let _ = writeln!(w, "  jmp .L.continue.{c}");
+                let _ = writeln!(w, ".L.break.{c}:");
+            }
+            NodeKind::For(init, condition, increment, body) => {
+                self.count += 1;
+                let c = self.count;
+                if let Some(init) = init.as_ref() {
+                    self.gen_statement(w, init);
+                }
+                let _ = writeln!(w, ".L.begin.{c}:");
+                if let Some(condition) = condition.as_ref() {
+                    self.gen_expression(w, condition);
+                    self.cmp_zero(w, condition.ty.as_ref().unwrap());
+                    let _ = writeln!(w, "  je  .L.break.{c}");
+                }
+                self.gen_loop_body(w, body, c);
+                let _ = writeln!(w, ".L.continue.{c}:");
+                if let Some(increment) = increment.as_ref() {
+                    self.gen_expression(w, increment);
+                }
+                let _ = writeln!(w, "  jmp .L.begin.{c}");
+                let _ = writeln!(w, ".L.break.{c}:");
+            }
+            NodeKind::Do(body, condition) => {
+                self.count += 1;
+                let c = self.count;
+                let _ = writeln!(w, ".L.begin.{c}:");
+                self.gen_loop_body(w, body, c);
+                let _ = writeln!(w, ".L.continue.{c}:");
+                self.gen_expression(w, condition);
+                self.cmp_zero(w, condition.ty.as_ref().unwrap());
+                let _ = writeln!(w, "  jne .L.begin.{c}");
+                let _ = writeln!(w, ".L.break.{c}:");
+            }
+            NodeKind::Switch {
+                condition,
+                body,
+                cases,
+                has_default,
+            } => {
+                self.count += 1;
+                let c = self.count;
+                self.gen_expression(w, condition);
+
+                let (ax, di) = regs(condition.ty.as_ref().unwrap());
+                for (i, value) in cases.iter().enumerate() {
+                    if i32::try_from(*value).is_ok() || ax == "%eax" {
+                        let _ = writeln!(w, "  cmp ${value}, {ax}");
+                    } else {
+                        // 32 ビットの即値に収まらない値はレジスタを経由して比較する
+                        let _ = writeln!(w, "  mov ${value}, {di}");
+                        let _ = writeln!(w, "  cmp {di}, {ax}");
+                    }
+                    let _ = writeln!(w, "  je .L.case.{c}.{i}");
+                }
+
+                if *has_default {
+                    let _ = writeln!(w, "  jmp .L.default.{c}");
+                } else {
+                    let _ = writeln!(w, "  jmp .L.break.{c}");
+                }
+
+                self.break_labels.push(format!(".L.break.{c}"));
+                self.switches.push(c);
+                self.gen_statement(w, body);
+                self.switches.pop();
+                self.break_labels.pop();
+
+                let _ = writeln!(w, ".L.break.{c}:");
+            }
+            NodeKind::Case(i, statement) => {
+                let _ = writeln!(w, ".L.case.{}.{i}:", self.switches.last().unwrap());
+                self.gen_statement(w, statement);
+            }
+            NodeKind::Default(statement) => {
+                let _ = writeln!(w, ".L.default.{}:", self.switches.last().unwrap());
+                self.gen_statement(w, statement);
+            }
+            NodeKind::Break => {
+                let _ = writeln!(w, "  jmp {}", self.break_labels.last().unwrap());
+            }
+            NodeKind::Continue => {
+                let _ = writeln!(w, "  jmp {}", self.continue_labels.last().unwrap());
+            }
+            NodeKind::Goto(name) => {
+                let _ = writeln!(w, "  jmp .L.label.{}.{name}", self.function_name);
+            }
+            NodeKind::Label(name, statement) => {
+                let _ = writeln!(w, ".L.label.{}.{name}:", self.function_name);
+                self.gen_statement(w, statement);
             }
             NodeKind::ExpressionStatement => self.gen_expression(w, node.lhs.as_ref().unwrap()),
             _ => {}
         }
     }
 
+    /// ループの本体を、その中の break と continue の飛び先を設定して出力する
+    fn gen_loop_body<W: Write>(&mut self, w: &mut W, body: &Node, c: i32) {
+        self.break_labels.push(format!(".L.break.{c}"));
+        self.continue_labels.push(format!(".L.continue.{c}"));
+        self.gen_statement(w, body);
+        self.continue_labels.pop();
+        self.break_labels.pop();
+    }
+
     fn gen_expression<W: Write>(&mut self, w: &mut W, node: &Node) {
         match node.kind {
             NodeKind::Num(n) => {
-                let _ = writeln!(w, "  mov ${n}, %rax");
+                // mov の即値は 32 ビットまでなので、それより大きい値は movabs で読み込む
+                if i32::try_from(n).is_ok() {
+                    let _ = writeln!(w, "  mov ${n}, %rax");
+                } else {
+                    let _ = writeln!(w, "  movabs ${n}, %rax");
+                }
                 return;
             }
-            NodeKind::Var(_) => {
+            NodeKind::VaArg => {
+                // 整数は reg_save_area の整数レジスタ部分、浮動小数点数は XMM レジスタ部分から
+                // 順に取り出し、使い切ったら overflow_arg_area（スタックで渡された引数）から取り出す
+                let (offset, limit, step) = match node.ty.as_ref().unwrap().base() {
+                    Some(ty) if ty.is_flonum() => (4, ARG_REGS.len() * 8 + FP_ARG_REGS * 16, 16),
+                    _ => (0, ARG_REGS.len() * 8, 8),
+                };
+
+                self.count += 1;
+                let c = self.count;
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                let _ = writeln!(w, "  cmpl ${}, {offset}(%rax)", limit - step);
+                let _ = writeln!(w, "  ja .L.va_arg.stack.{c}");
+                let _ = writeln!(w, "  movl {offset}(%rax), %edx");
+                let _ = writeln!(w, "  add 16(%rax), %rdx");
+                let _ = writeln!(w, "  addl ${step}, {offset}(%rax)");
+                let _ = writeln!(w, "  mov %rdx, %rax");
+                let _ = writeln!(w, "  jmp .L.va_arg.end.{c}");
+                let _ = writeln!(w, ".L.va_arg.stack.{c}:");
+                let _ = writeln!(w, "  mov 8(%rax), %rdx");
+                let _ = writeln!(w, "  addq $8, 8(%rax)");
+                let _ = writeln!(w, "  mov %rdx, %rax");
+                let _ = writeln!(w, ".L.va_arg.end.{c}:");
+                return;
+            }
+            NodeKind::FNum(value) => {
+                // 即値を直接 %xmm0 に入れる命令はないので、ビット列を %rax 経由で移す
+                if let Some(Type::Float) = node.ty {
+                    let _ = writeln!(w, "  mov ${}, %eax", (value as f32).to_bits());
+                } else {
+                    let _ = writeln!(w, "  movabs ${}, %rax", value.to_bits());
+                }
+                let _ = writeln!(w, "  movq %rax, %xmm0");
+                return;
+            }
+            NodeKind::MemZero(ref var) => {
+                // %rdi から %rcx バイトを %al で埋める
+                let _ = writeln!(w, "  mov ${}, %rcx", var.ty.size());
+                let _ = writeln!(w, "  lea {}(%rbp), %rdi", var.offset.get());
+                let _ = writeln!(w, "  mov $0, %al");
+                let _ = writeln!(w, "  rep stosb");
+                return;
+            }
+            NodeKind::Var(_) | NodeKind::Member(_) => {
                 self.gen_address(w, node);
-                let _ = writeln!(w, "  mov (%rax), %rax");
+                self.load(w, node.ty.as_ref().unwrap());
+                return;
+            }
+            NodeKind::Cast => {
+                let lhs = node.lhs.as_ref().unwrap();
+                self.gen_expression(w, lhs);
+                self.cast(w, lhs.ty.as_ref().unwrap(), node.ty.as_ref().unwrap());
+                return;
+            }
+            NodeKind::Addr => {
+                self.gen_address(w, node.lhs.as_ref().unwrap());
+                return;
+            }
+            NodeKind::Deref => {
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                self.load(w, node.ty.as_ref().unwrap());
+                return;
+            }
+            NodeKind::Not => {
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                self.cmp_zero(w, node.lhs.as_ref().unwrap().ty.as_ref().unwrap());
+                let _ = writeln!(w, "  sete %al");
+                let _ = writeln!(w, "  movzb %al, %rax");
+                return;
+            }
+            NodeKind::Neg => {
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                // 浮動小数点数は符号ビットだけを反転する。0 から引くと -0.0 が作れない
+                match node.ty.as_ref().unwrap() {
+                    Type::Float => {
+                        let _ = writeln!(w, "  mov $1, %rax");
+                        let _ = writeln!(w, "  shl $31, %rax");
+                        let _ = writeln!(w, "  movq %rax, %xmm1");
+                        let _ = writeln!(w, "  xorps %xmm1, %xmm0");
+                    }
+                    Type::Double => {
+                        let _ = writeln!(w, "  mov $1, %rax");
+                        let _ = writeln!(w, "  shl $63, %rax");
+                        let _ = writeln!(w, "  movq %rax, %xmm1");
+                        let _ = writeln!(w, "  xorpd %xmm1, %xmm0");
+                    }
+                    _ => {
+                        let _ = writeln!(w, "  neg %rax");
+                    }
+                }
+                return;
+            }
+            NodeKind::BitNot => {
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                let _ = writeln!(w, "  not %rax");
+                return;
+            }
+            NodeKind::LogicalAnd => {
+                self.count += 1;
+                let c = self.count;
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                self.cmp_zero(w, node.lhs.as_ref().unwrap().ty.as_ref().unwrap());
+                let _ = writeln!(w, "  je .L.false.{c}");
+                self.gen_expression(w, node.rhs.as_ref().unwrap());
+                self.cmp_zero(w, node.rhs.as_ref().unwrap().ty.as_ref().unwrap());
+                let _ = writeln!(w, "  je .L.false.{c}");
+                let _ = writeln!(w, "  mov $1, %rax");
+                let _ = writeln!(w, "  jmp .L.end.{c}");
+                let _ = writeln!(w, ".L.false.{c}:");
+                let _ = writeln!(w, "  mov $0, %rax");
+                let _ = writeln!(w, ".L.end.{c}:");
+                return;
+            }
+            NodeKind::LogicalOr => {
+                self.count += 1;
+                let c = self.count;
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                self.cmp_zero(w, node.lhs.as_ref().unwrap().ty.as_ref().unwrap());
+                let _ = writeln!(w, "  jne .L.true.{c}");
+                self.gen_expression(w, node.rhs.as_ref().unwrap());
+                self.cmp_zero(w, node.rhs.as_ref().unwrap().ty.as_ref().unwrap());
+                let _ = writeln!(w, "  jne .L.true.{c}");
+                let _ = writeln!(w, "  mov $0, %rax");
+                let _ = writeln!(w, "  jmp .L.end.{c}");
+                let _ = writeln!(w, ".L.true.{c}:");
+                let _ = writeln!(w, "  mov $1, %rax");
+                let _ = writeln!(w, ".L.end.{c}:");
+                return;
+            }
+            NodeKind::Conditional(ref condition, ref then, ref els) => {
+                self.count += 1;
+                let c = self.count;
+                self.gen_expression(w, condition);
+                self.cmp_zero(w, condition.ty.as_ref().unwrap());
+                let _ = writeln!(w, "  je .L.else.{c}");
+                self.gen_expression(w, then);
+                let _ = writeln!(w, "  jmp .L.end.{c}");
+                let _ = writeln!(w, ".L.else.{c}:");
+                self.gen_expression(w, els);
+                let _ = writeln!(w, ".L.end.{c}:");
+                return;
+            }
+            NodeKind::Comma => {
+                self.gen_expression(w, node.lhs.as_ref().unwrap());
+                self.gen_expression(w, node.rhs.as_ref().unwrap());
                 return;
             }
             NodeKind::Assign => {
                 self.gen_address(w, node.lhs.as_ref().unwrap());
-                let _ = writeln!(w, "  push %rax");
+                self.push(w);
                 self.gen_expression(w, node.rhs.as_ref().unwrap());
-                let _ = writeln!(w, "  pop %rdi");
-                let _ = writeln!(w, "  mov %rax, (%rdi)");
+                self.store(w, node.ty.as_ref().unwrap());
+                return;
+            }
+            NodeKind::FunCall(ref name, ref args, ref ret_buffer) => {
+                // 16 バイトを超える構造体を返す関数には、戻り値の書き込み先を隠れた第1引数として渡す
+                let ret_ty = node.ty.as_ref().unwrap();
+                let ret_ptr = ret_buffer.as_ref().filter(|_| classify(ret_ty).is_none());
+
+                let on_stack = pass_on_stack(
+                    args.iter().map(|arg| arg.ty.as_ref().unwrap()),
+                    ret_ptr.is_some() as usize,
+                );
+                let stack_args: i32 = args
+                    .iter()
+                    .zip(&on_stack)
+                    .filter(|(_, &on_stack)| on_stack)
+                    .map(|(arg, _)| stack_slots(arg.ty.as_ref().unwrap()))
+                    .sum();
+
+                // call 命令の時点で %rsp が16バイト境界に揃うようにする
+                let padding = (self.depth + stack_args) % 2 == 1;
+                if padding {
+                    let _ = writeln!(w, "  sub $8, %rsp");
+                    self.depth += 1;
+                }
+
+                // スタックで渡す引数を後ろから積み、その上にレジスタで渡す引数を積む
+                for pass_on_stack in [true, false] {
+                    for (arg, _) in args
+                        .iter()
+                        .zip(&on_stack)
+                        .rev()
+                        .filter(|(_, &on_stack)| on_stack == pass_on_stack)
+                    {
+                        self.gen_expression(w, arg);
+                        match arg.ty.as_ref().unwrap() {
+                            ty @ (Type::Struct(_) | Type::Union(_)) => self.push_struct(w, ty),
+                            ty if ty.is_flonum() => self.pushf(w),
+                            _ => self.push(w),
+                        }
+                    }
+                }
+
+                // 構造体は 8 バイトごとに分類に従ってレジスタに読み込む
+                let (mut gp, mut fp) = (ret_ptr.is_some() as usize, 0);
+                for (arg, _) in args
+                    .iter()
+                    .zip(&on_stack)
+                    .filter(|(_, &on_stack)| !on_stack)
+                {
+                    for class in classify(arg.ty.as_ref().unwrap()).unwrap() {
+                        match class {
+                            ArgClass::Sse => {
+                                self.popf(w, fp);
+                                fp += 1;
+                            }
+                            ArgClass::Integer => {
+                                self.pop(w, ARG_REGS[gp]);
+                                gp += 1;
+                            }
+                        }
+                    }
+                }
+
+                if let Some(ret_ptr) = ret_ptr {
+                    let _ = writeln!(w, "  lea {}(%rbp), %rdi", ret_ptr.offset.get());
+                }
+
+                // 可変長引数の関数のために、%al に XMM レジスタで渡した引数の個数を入れておく
+                let _ = writeln!(w, "  mov ${fp}, %rax");
+                let _ = writeln!(w, "  call {name}");
+
+                // 戻り値の上位ビットは不定なので、int より小さい型は拡張しておく
+                match node.ty.as_ref().unwrap() {
+                    Type::Char => {
+                        let _ = writeln!(w, "  movsbl %al, %eax");
+                    }
+                    Type::UChar => {
+                        let _ = writeln!(w, "  movzbl %al, %eax");
+                    }
+                    Type::Short => {
+                        let _ = writeln!(w, "  movswl %ax, %eax");
+                    }
+                    Type::UShort => {
+                        let _ = writeln!(w, "  movzwl %ax, %eax");
+                    }
+                    _ => {}
+                }
+
+                // 戻り値の構造体を受け取る領域のアドレスを値とする
+                if let Some(ret_buffer) = ret_buffer {
+                    if ret_ptr.is_some() {
+                        let _ = writeln!(w, "  lea {}(%rbp), %rax", ret_buffer.offset.get());
+                    } else {
+                        self.copy_ret_buffer(w, ret_ty, ret_buffer.offset.get());
+                    }
+                }
+
+                if stack_args > 0 {
+                    let _ = writeln!(w, "  add ${}, %rsp", stack_args * 8);
+                    self.depth -= stack_args;
+                }
+
+                if padding {
+                    let _ = writeln!(w, "  add $8, %rsp");
+                    self.depth -= 1;
+                }
                 return;
             }
             _ => {}
         }
 
+        if node.lhs.as_ref().unwrap().ty.as_ref().unwrap().is_flonum() {
+            self.gen_float_binary(w, node);
+            return;
+        }
+
         self.gen_expression(w, node.rhs.as_ref().unwrap());
-        let _ = writeln!(w, "  push %rax");
+        self.push(w);
+
+        let lhs = node.lhs.as_ref().unwrap();
+        self.gen_expression(w, lhs);
+        self.pop(w, "%rdi");
 
-        self.gen_expression(w, node.lhs.as_ref().unwrap());
-        let _ = writeln!(w, "  pop %rdi");
+        let ty = lhs.ty.as_ref().unwrap();
+        let (ax, di) = regs(ty);
 
         match node.kind {
             NodeKind::Add => {
-                let _ = writeln!(w, "  add %rdi, %rax");
+                let _ = writeln!(w, "  add {di}, {ax}");
             }
             NodeKind::Sub => {
-                let _ = writeln!(w, "  sub %rdi, %rax");
+                let _ = writeln!(w, "  sub {di}, {ax}");
             }
             NodeKind::Multiply => {
-                let _ = writeln!(w, "  imul %rdi, %rax");
+                let _ = writeln!(w, "  imul {di}, {ax}");
+            }
+            NodeKind::Div | NodeKind::Mod => {
+                if ty.is_unsigned() {
+                    let _ = writeln!(w, "  mov $0, %rdx");
+                    let _ = writeln!(w, "  div {di}");
+                } else {
+                    if ty.size() == 8 {
+                        let _ = writeln!(w, "  cqo");
+                    } else {
+                        let _ = writeln!(w, "  cdq");
+                    }
+                    let _ = writeln!(w, "  idiv {di}");
+                }
+
+                // 剰余は %rdx に入る
+                if let NodeKind::Mod = node.kind {
+                    let _ = writeln!(w, "  mov %rdx, %rax");
+                }
+            }
+            NodeKind::BitAnd => {
+                let _ = writeln!(w, "  and {di}, {ax}");
+            }
+            NodeKind::BitOr => {
+                let _ = writeln!(w, "  or {di}, {ax}");
+            }
+            NodeKind::BitXor => {
+                let _ = writeln!(w, "  xor {di}, {ax}");
+            }
+            NodeKind::ShiftLeft => {
+                // シフト量は %cl で指定する
+                let _ = writeln!(w, "  mov %rdi, %rcx");
+                let _ = writeln!(w, "  shl %cl, {ax}");
+            }
+            NodeKind::ShiftRight => {
+                // 符号なしの値は論理シフト、符号付きの値は算術シフトする
+                let _ = writeln!(w, "  mov %rdi, %rcx");
+                if ty.is_unsigned() {
+                    let _ = writeln!(w, "  shr %cl, {ax}");
+                } else {
+                    let _ = writeln!(w, "  sar %cl, {ax}");
+                }
+            }
+            NodeKind::Equal
+            | NodeKind::NotEqual
+            | NodeKind::LessThan
+            | NodeKind::LessThanOrEqual => {
+                let _ = writeln!(w, "  cmp {di}, {ax}");
+
+                let insn = match (&node.kind, ty.is_unsigned()) {
+                    (NodeKind::Equal, _) => "sete",
+                    (NodeKind::NotEqual, _) => "setne",
+                    (NodeKind::LessThan, false) => "setl",
+                    (NodeKind::LessThan, true) => "setb",
+                    (NodeKind::LessThanOrEqual, false) => "setle",
+                    (NodeKind::LessThanOrEqual, true) => "setbe",
+                    _ => unreachable!(),
+                };
+                let _ = writeln!(w, "  {insn} %al");
+                let _ = writeln!(w, "  movzb %al, %rax");
+            }
+            _ => {}
+        }
+    }
+
+    /// 浮動小数点数の二項演算。左辺を %xmm0、右辺を %xmm1 に置いて計算する
+    fn gen_float_binary<W: Write>(&mut self, w: &mut W, node: &Node) {
+        self.gen_expression(w, node.rhs.as_ref().unwrap());
+        self.pushf(w);
+
+        let lhs = node.lhs.as_ref().unwrap();
+        self.gen_expression(w, lhs);
+        self.popf(w, 1);
+
+        let sz = fp_suffix(lhs.ty.as_ref().unwrap());
+
+        match node.kind {
+            NodeKind::Add => {
+                let _ = writeln!(w, "  add{sz} %xmm1, %xmm0");
+            }
+            NodeKind::Sub => {
+                let _ = writeln!(w, "  sub{sz} %xmm1, %xmm0");
+            }
+            NodeKind::Multiply => {
+                let _ = writeln!(w, "  mul{sz} %xmm1, %xmm0");
             }
             NodeKind::Div => {
-                let _ = writeln!(w, "  cqo");
-                let _ = writeln!(w, "  idiv %rdi");
+                let _ = writeln!(w, "  div{sz} %xmm1, %xmm0");
             }
             NodeKind::Equal
             | NodeKind::NotEqual
             | NodeKind::LessThan
             | NodeKind::LessThanOrEqual => {
-                let _ = writeln!(w, "  cmp %rdi, %rax");
+                // 右辺と左辺を比べるので、lhs < rhs は rhs > lhs (seta) になる。
+                // どちらかが NaN のときはパリティフラグが立つ
+                let _ = writeln!(w, "  ucomi{sz} %xmm0, %xmm1");
 
                 match node.kind {
                     NodeKind::Equal => {
                         let _ = writeln!(w, "  sete %al");
+                        let _ = writeln!(w, "  setnp %dl");
+                        let _ = writeln!(w, "  and %dl, %al");
                     }
                     NodeKind::NotEqual => {
                         let _ = writeln!(w, "  setne %al");
+                        let _ = writeln!(w, "  setp %dl");
+                        let _ = writeln!(w, "  or %dl, %al");
                     }
                     NodeKind::LessThan => {
-                        let _ = writeln!(w, "  setl %al");
+                        let _ = writeln!(w, "  seta %al");
                     }
-                    NodeKind::LessThanOrEqual => {
-                        let _ = writeln!(w, "  setle %al");
+                    _ => {
+                        let _ = writeln!(w, "  setae %al");
                     }
-                    _ => {}
                 }
 
+                let _ = writeln!(w, "  and $1, %al");
                 let _ = writeln!(w, "  movzb %al, %rax");
             }
-            _ => {}
+            _ => panic!("浮動小数点数には使えない演算です: {node:?}"),
+        }
+    }
+
+    /// 可変長引数の関数の先頭で、va_start がコピーする va_list のヘッダを組み立て、
+    /// 引数レジスタを全て退避する。退避領域は整数レジスタに8バイト、XMM レジスタに16バイトずつ
+    fn gen_va_area<W: Write>(
+        &mut self,
+        w: &mut W,
+        function: &Function,
+        on_stack: &[bool],
+        off: i32,
+    ) {
+        let (mut gp, mut fp, mut stack) = (self.ret_ptr_offset.is_some() as usize, 0, 0);
+        for (param, &on_stack) in function.params.iter().zip(on_stack) {
+            if on_stack {
+                stack += stack_slots(&param.ty);
+                continue;
+            }
+            for class in classify(&param.ty).unwrap() {
+                match class {
+                    ArgClass::Sse => fp += 1,
+                    ArgClass::Integer => gp += 1,
+                }
+            }
+        }
+
+        // gp_offset, fp_offset, overflow_arg_area, reg_save_area
+        let _ = writeln!(w, "  movl ${}, {off}(%rbp)", gp * 8);
+        let _ = writeln!(
+            w,
+            "  movl ${}, {}(%rbp)",
+            ARG_REGS.len() * 8 + fp * 16,
+            off + 4
+        );
+        let _ = writeln!(w, "  lea {}(%rbp), %rax", 16 + stack * 8);
+        let _ = writeln!(w, "  mov %rax, {}(%rbp)", off + 8);
+        let _ = writeln!(w, "  lea {}(%rbp), %rax", off + 24);
+        let _ = writeln!(w, "  mov %rax, {}(%rbp)", off + 16);
+
+        for (i, reg) in ARG_REGS.iter().enumerate() {
+            let _ = writeln!(w, "  mov {reg}, {}(%rbp)", off + 24 + i as i32 * 8);
+        }
+        for i in 0..FP_ARG_REGS {
+            let _ = writeln!(w, "  movsd %xmm{i}, {}(%rbp)", off + 72 + i as i32 * 16);
         }
     }
 
-    fn assign_lvar_offset(&mut self, function: &Program) {
+    fn assign_lvar_offset(&mut self, function: &Function) {
+        // 16 バイトを超える構造体を返す関数は、戻り値の書き込み先を %rdi で受け取る
+        let has_ret_ptr = classify(&function.return_ty).is_none();
+
+        // レジスタに収まらなかった引数は呼び出し元のスタックに積まれている
+        let on_stack = pass_on_stack(
+            function.params.iter().map(|param| &param.ty),
+            has_ret_ptr as usize,
+        );
+        let stack_params: Vec<_> = function
+            .params
+            .iter()
+            .zip(&on_stack)
+            .filter(|(_, &on_stack)| on_stack)
+            .map(|(param, _)| param)
+            .collect();
+        let mut slot = 0;
+        for param in &stack_params {
+            param.offset.set(16 + slot * 8);
+            slot += stack_slots(&param.ty);
+        }
+
         let mut offset = 0;
-        for var in &function.locals {
+        self.ret_ptr_offset = None;
+        if has_ret_ptr {
             offset += 8;
-            self.locals.insert(var.clone(), -offset);
+            self.ret_ptr_offset = Some(-offset);
+        }
+
+        for var in &function.locals {
+            if stack_params.iter().any(|param| Rc::ptr_eq(param, var)) {
+                continue;
+            }
+            offset += var.ty.size();
+            offset = align_to(offset, var.ty.align());
+            var.offset.set(-offset);
         }
         self.stack_size = align_to(offset, 16);
     }
 
-    pub fn codegen<W: Write>(&mut self, w: &mut W, function: Program) {
-        self.assign_lvar_offset(&function);
+    pub fn codegen<W: Write>(&mut self, w: &mut W, program: Program) {
+        self.emit_data(w, &program.globals);
+
+        for function in &program.functions {
+            self.gen_function(w, function);
+        }
+    }
+
+    fn emit_data<W: Write>(&mut self, w: &mut W, globals: &[Rc<Obj>]) {
+        for var in globals {
+            if !var.is_static {
+                let _ = writeln!(w, "  .globl {}", var.name);
+            }
 
-        let _ = writeln!(w, "  .globl main");
-        let _ = writeln!(w, "main:");
+            match &var.init_data {
+                Some(init_data) => {
+                    if var.is_read_only {
+                        let _ = writeln!(w, "  .section .rodata");
+                    } else {
+                        let _ = writeln!(w, "  .data");
+                    }
+                    let _ = writeln!(w, "  .align {}", var.ty.align());
+                    let _ = writeln!(w, "{}:", var.name);
+
+                    // 他の大域変数のアドレスを指す箇所は、リンク時に埋めるよう .quad で出力する
+                    let mut pos = 0;
+                    while pos < init_data.len() {
+                        match var.relocations.iter().find(|reloc| reloc.offset == pos) {
+                            Some(reloc) => {
+                                let _ = writeln!(w, "  .quad {}{:+}", reloc.label, reloc.addend);
+                                pos += 8;
+                            }
+                            None => {
+                                let _ = writeln!(w, "  .byte {}", init_data[pos]);
+                                pos += 1;
+                            }
+                        }
+                    }
+                }
+                None => {
+                    let _ = writeln!(w, "  .bss");
+                    let _ = writeln!(w, "  .align {}", var.ty.align());
+                    let _ = writeln!(w, "{}:", var.name);
+                    let _ = writeln!(w, "  .zero {}", var.ty.size());
+                }
+            }
+        }
+    }
+
+    fn gen_function<W: Write>(&mut self, w: &mut W, function: &Function) {
+        self.assign_lvar_offset(function);
+        self.function_name = function.name.clone();
+
+        let _ = writeln!(w, "  .globl {}", function.name);
+        let _ = writeln!(w, "  .text");
+        let _ = writeln!(w, "{}:", function.name);
 
         let _ = writeln!(w, "  push %rbp");
         let _ = writeln!(w, "  mov %rsp, %rbp");
         let _ = writeln!(w, "  sub ${}, %rsp", self.stack_size);
 
+        // レジスタで渡された引数をスタックに退避する
+        let mut gp = 0;
+        if let Some(offset) = self.ret_ptr_offset {
+            let _ = writeln!(w, "  mov %rdi, {offset}(%rbp)");
+            gp += 1;
+        }
+
+        let on_stack = pass_on_stack(function.params.iter().map(|param| &param.ty), gp);
+        if let Some(va_area) = &function.va_area {
+            self.gen_va_area(w, function, &on_stack, va_area.offset.get());
+        }
+
+        // 構造体は 8 バイトごとに、分類に従ったレジスタから書き込む
+        let mut fp = 0;
+        for (param, _) in function.params.iter().zip(&on_stack).filter(|(_, &s)| !s) {
+            for (i, class) in classify(&param.ty).unwrap().into_iter().enumerate() {
+                let i = i as i32;
+                let offset = param.offset.get() + i * 8;
+                let size = (param.ty.size() - i * 8).min(8);
+                match class {
+                    ArgClass::Sse => {
+                        self.store_fp(w, fp, offset, size);
+                        fp += 1;
+                    }
+                    ArgClass::Integer => {
+                        let regs = [ARG_REGS[gp], ARG_REGS32[gp], ARG_REGS16[gp], ARG_REGS8[gp]];
+                        self.store_gp(w, regs, offset, size);
+                        gp += 1;
+                    }
+                }
+            }
+        }
+
         self.gen_statement(w, &function.body);
+        assert_eq!(self.depth, 0);
 
-        let _ = writeln!(w, ".L.return:");
+        let _ = writeln!(w, ".L.return.{}:", function.name);
         let _ = writeln!(w, "  mov %rbp, %rsp");
         let _ = writeln!(w, "  pop %rbp");
         let _ = writeln!(w, "  ret");
     }
 }
-
-fn align_to(n: i32, align: i32) -> i32 {
-    (n + align - 1) / align * align
-}
@@ -4,13 +4,12 @@ use std::{
     process::Command,
 };
 
-use node::Program;
-
 mod codegen;
 mod node;
 mod parser;
 mod token;
 mod tokenizer;
+mod types;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -53,15 +52,20 @@ fn write_asm<W: Write>(w: &mut W, input: &str) {
     // Parse
     //
     let mut parser = parser::Parser::new(tokens);
-    let (body, locals) = parser.parse();
+    let program = parser.parse();
 
     //
     // Codegen
     //
-    codegen::Generator::new().codegen(w, Program { body, locals });
+    codegen::Generator::new().codegen(w, program);
 }
 
 fn run(input: &str) -> i32 {
+    run_with_object(input, None)
+}
+
+/// `object` に C のソースを渡すと、それをコンパイルしたオブジェクトも一緒にリンクして実行する
+fn run_with_object(input: &str, object: Option<&str>) -> i32 {
     let mut asm_file = tempfile::NamedTempFile::new().expect("一時ファイルの作成に失敗しました");
     write_asm(&mut asm_file, input);
 
@@ -73,12 +77,30 @@ fn run(input: &str) -> i32 {
     };
     let binary_file_path_str = format!("{asm_file_path_str}.bin");
 
-    let _ = Command::new("cc")
+    let mut command = Command::new("cc");
+    command
         .arg("-x")
         .arg("assembler")
         .arg("-o")
         .arg(&binary_file_path_str)
-        .arg(asm_file_path_str)
+        .arg(asm_file_path_str);
+
+    let object_file = object.map(|source| {
+        let mut object_file = tempfile::Builder::new()
+            .suffix(".c")
+            .tempfile()
+            .expect("一時ファイルの作成に失敗しました");
+        object_file
+            .write_all(source.as_bytes())
+            .expect("オブジェクトのソースの書き込みに失敗しました");
+        object_file
+    });
+
+    if let Some(object_file) = &object_file {
+        command.arg("-x").arg("c").arg(object_file.path());
+    }
+
+    let _ = command
         .output()
         .expect("アセンブリのコンパイルに失敗しました");
 
@@ -89,6 +111,9 @@ fn run(input: &str) -> i32 {
         .unwrap();
 
     let _ = asm_file.close();
+    if let Some(object_file) = object_file {
+        let _ = object_file.close();
+    }
 
     fs::remove_file(&binary_file_path_str).expect("バイナリファイルの削除に失敗しました");
 
@@ -97,114 +122,121 @@ fn run(input: &str) -> i32 {
 
 #[cfg(test)]
 mod test {
-    use crate::run;
+    use crate::{run, run_with_object};
 
     #[test]
     fn test_numbers() {
-        assert_eq!(run("{ 0; }"), 0);
-        assert_eq!(run("{ 42; }"), 42);
+        assert_eq!(run("int main() { 0; }"), 0);
+        assert_eq!(run("int main() { 42; }"), 42);
     }
 
     #[test]
     fn test_add_sub() {
-        assert_eq!(run("{ 5+20-4; }"), 21);
+        assert_eq!(run("int main() { 5+20-4; }"), 21);
     }
 
     #[test]
     fn test_with_space() {
-        assert_eq!(run(" { 12 + 34 -  5 ;  }"), 41);
+        assert_eq!(run(" int main() { 12 + 34 -  5 ;  }"), 41);
     }
 
     #[test]
     fn test_mul() {
-        assert_eq!(run("{ 5+6*7; }"), 47);
+        assert_eq!(run("int main() { 5+6*7; }"), 47);
     }
 
     #[test]
     fn test_primary() {
-        assert_eq!(run("{5*(9-6);}"), 15);
-        assert_eq!(run("{(3+5)/2;}"), 4);
+        assert_eq!(run("int main() {5*(9-6);}"), 15);
+        assert_eq!(run("int main() {(3+5)/2;}"), 4);
     }
 
     #[test]
     fn test_unary() {
-        assert_eq!(run("{-10+20;}"), 10);
+        assert_eq!(run("int main() {-10+20;}"), 10);
     }
 
     #[test]
     fn test_eq() {
-        assert_eq!(run("{0==1;}"), 0);
-        assert_eq!(run("{42==42;}"), 1);
-        assert_eq!(run("{0!=1;}"), 1);
-        assert_eq!(run("{42!=42;}"), 0);
+        assert_eq!(run("int main() {0==1;}"), 0);
+        assert_eq!(run("int main() {42==42;}"), 1);
+        assert_eq!(run("int main() {0!=1;}"), 1);
+        assert_eq!(run("int main() {42!=42;}"), 0);
     }
 
     #[test]
     fn test_greater_than() {
-        assert_eq!(run("{0<1;}"), 1);
-        assert_eq!(run("{1<1;}"), 0);
-        assert_eq!(run("{2<1;}"), 0);
+        assert_eq!(run("int main() {0<1;}"), 1);
+        assert_eq!(run("int main() {1<1;}"), 0);
+        assert_eq!(run("int main() {2<1;}"), 0);
     }
 
     #[test]
     fn test_greater_eq_than() {
-        assert_eq!(run("{0<=1;}"), 1);
-        assert_eq!(run("{1<=1;}"), 1);
-        assert_eq!(run("{2<=1;}"), 0);
+        assert_eq!(run("int main() {0<=1;}"), 1);
+        assert_eq!(run("int main() {1<=1;}"), 1);
+        assert_eq!(run("int main() {2<=1;}"), 0);
     }
 
     #[test]
     fn test_less_than() {
-        assert_eq!(run("{1>0;}"), 1);
-        assert_eq!(run("{1>1;}"), 0);
-        assert_eq!(run("{1>2;}"), 0);
+        assert_eq!(run("int main() {1>0;}"), 1);
+        assert_eq!(run("int main() {1>1;}"), 0);
+        assert_eq!(run("int main() {1>2;}"), 0);
     }
 
     #[test]
     fn test_less_eq_than() {
-        assert_eq!(run("{1>=0;}"), 1);
-        assert_eq!(run("{1>=1;}"), 1);
-        assert_eq!(run("{1>=2;}"), 0);
+        assert_eq!(run("int main() {1>=0;}"), 1);
+        assert_eq!(run("int main() {1>=1;}"), 1);
+        assert_eq!(run("int main() {1>=2;}"), 0);
     }
 
     #[test]
     fn test_multiple_statements() {
-        assert_eq!(run("{1; 2; 3;}"), 3);
+        assert_eq!(run("int main() {1; 2; 3;}"), 3);
     }
 
     #[test]
     fn test_single_letter_var() {
-        assert_eq!(run("{a = 5;}"), 5);
-        assert_eq!(run("{k = 40;}"), 40);
-        assert_eq!(run("{a = 12; b = 3; a * b;}"), 36);
+        assert_eq!(run("int main() {int a; a = 5;}"), 5);
+        assert_eq!(run("int main() {int k; k = 40;}"), 40);
+        assert_eq!(run("int main() {int a; int b; a = 12; b = 3; a * b;}"), 36);
     }
 
     #[test]
     fn test_multi_letter_var() {
-        assert_eq!(run("{test = 5;}"), 5);
-        assert_eq!(run("{returnx = 5;}"), 5);
-        assert_eq!(run("{r906 = 96;}"), 96);
-        assert_eq!(run("{r_906 = 5;}"), 5);
-        assert_eq!(run("{pi = 3; r = 5; r * r * pi;}"), 75);
+        assert_eq!(run("int main() {int test; test = 5;}"), 5);
+        assert_eq!(run("int main() {int returnx; returnx = 5;}"), 5);
+        assert_eq!(run("int main() {int r906; r906 = 96;}"), 96);
+        assert_eq!(run("int main() {int r_906; r_906 = 5;}"), 5);
+        assert_eq!(
+            run("int main() {int pi, r; pi = 3; r = 5; r * r * pi;}"),
+            75
+        );
     }
 
     #[test]
     fn test_return_statement() {
-        assert_eq!(run("{42; return 30; 4;}"), 30);
-        assert_eq!(run("{return a = 5;}"), 5);
-        assert_eq!(run("{a = 10; b = 12; return a * b - b; 42;}"), 108);
+        assert_eq!(run("int main() {42; return 30; 4;}"), 30);
+        assert_eq!(run("int main() {int a; return a = 5;}"), 5);
+        assert_eq!(
+            run("int main() {int a, b; a = 10; b = 12; return a * b - b; 42;}"),
+            108
+        );
     }
 
     #[test]
     fn test_null_statement() {
-        assert_eq!(run("{ ;;; return 5; }"), 5);
+        assert_eq!(run("int main() { ;;; return 5; }"), 5);
     }
 
     #[test]
     fn test_if_statement() {
         assert_eq!(
             run("
-                { 
+                int main() { 
+                    int a, b;
                     if (1==2) { 
                         a = 5; 
                     } else { 
@@ -223,4 +255,1981 @@ mod test {
             100
         );
     }
+
+    #[test]
+    fn test_while_statement() {
+        assert_eq!(
+            run("int main() { int i; i = 0; while (i < 10) i = i + 1; return i; }"),
+            10
+        );
+        assert_eq!(
+            run("int main() { int i, j; i = 0; j = 0; while (i < 5) { j = j + i; i = i + 1; } return j; }"),
+            10
+        );
+        assert_eq!(run("int main() { while (0) return 1; return 2; }"), 2);
+    }
+
+    #[test]
+    fn test_for_statement() {
+        assert_eq!(
+            run("int main() { int i, j; j = 0; for (i = 0; i <= 10; i = i + 1) j = i + j; return j; }"),
+            55
+        );
+        assert_eq!(run("int main() { for (;;) return 3; return 5; }"), 3);
+        assert_eq!(
+            run("int main() { int i, j; j = 0; for (i = 0; i < 3; i = i + 1) if (i == 1) j = j + 10; else j = j + 1; return j; }"),
+            12
+        );
+    }
+
+    #[test]
+    fn test_declaration() {
+        assert_eq!(
+            run("int main() { int x; int y; x = 3; y = 5; return x + y; }"),
+            8
+        );
+        assert_eq!(
+            run("int main() { int x, y; x = 3; y = 5; return y - x; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { int x; { int y; y = 2; x = y; } return x; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { int *p, x; x = 7; p = &x; return *p; }"),
+            7
+        );
+    }
+
+    #[test]
+    fn test_function_call() {
+        let object = "
+            int ret3() { return 3; }
+            int ret5() { return 5; }
+            int add(int x, int y) { return x + y; }
+            int sub(int x, int y) { return x - y; }
+            int add6(int a, int b, int c, int d, int e, int f) {
+                return a + b + c + d + e + f;
+            }
+            int add8(int a, int b, int c, int d, int e, int f, int g, int h) {
+                return a + b + c + d + e + f + g + h;
+            }
+            int sub8(int a, int b, int c, int d, int e, int f, int g, int h) {
+                return a - b - c - d - e - f - g - h;
+            }
+        ";
+
+        assert_eq!(
+            run_with_object("int main() { return ret3(); }", Some(object)),
+            3
+        );
+        assert_eq!(
+            run_with_object("int main() { return ret5(); }", Some(object)),
+            5
+        );
+        assert_eq!(
+            run_with_object("int main() { return add(3, 5); }", Some(object)),
+            8
+        );
+        assert_eq!(
+            run_with_object("int main() { return sub(5, 3); }", Some(object)),
+            2
+        );
+        assert_eq!(
+            run_with_object(
+                "int main() { return add6(1, 2, 3, 4, 5, 6); }",
+                Some(object)
+            ),
+            21
+        );
+        assert_eq!(
+            run_with_object(
+                "int main() { return add8(1, 2, 3, 4, 5, 6, 7, 8); }",
+                Some(object)
+            ),
+            36
+        );
+        assert_eq!(
+            run_with_object(
+                "int main() { return sub8(100, 1, 2, 3, 4, 5, 6, 7) + add(1, add(2, 3)); }",
+                Some(object)
+            ),
+            78
+        );
+        assert_eq!(
+            run_with_object(
+                "int main() { int a; a = 1; return add8(a, a, a, a, a, a, a, ret3()); }",
+                Some(object)
+            ),
+            10
+        );
+    }
+
+    #[test]
+    fn test_function_definition() {
+        assert_eq!(
+            run("int main() { return ret32(); } int ret32() { return 32; }"),
+            32
+        );
+        assert_eq!(
+            run("int main() { return add2(3, 4); } int add2(int x, int y) { return x + y; }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { return sub2(4, 3); } int sub2(int x, int y) { return x - y; }"),
+            1
+        );
+        assert_eq!(
+            run("
+                int main() { return add8(1, 2, 3, 4, 5, 6, 7, 8) - sub8(1, 2, 3, 4, 5, 6, 7, 8); }
+                int add8(int a, int b, int c, int d, int e, int f, int g, int h) { return a + b + c + d + e + f + g + h; }
+                int sub8(int a, int b, int c, int d, int e, int f, int g, int h) { return h * 4 - g; }
+            "),
+            11
+        );
+    }
+
+    #[test]
+    fn test_recursion() {
+        assert_eq!(
+            run("
+                int main() { return fib(9); }
+                int fib(int x) { if (x <= 1) return 1; return fib(x - 1) + fib(x - 2); }
+            "),
+            55
+        );
+        assert_eq!(
+            run("
+                int main() { return factorial(5); }
+                int factorial(int n) { if (n == 0) return 1; return n * factorial(n - 1); }
+            "),
+            120
+        );
+    }
+
+    #[test]
+    fn test_address_and_dereference() {
+        assert_eq!(run("int main() { int x; x = 3; return *&x; }"), 3);
+        assert_eq!(
+            run("int main() { int x; int *y; int **z; x = 3; y = &x; z = &y; return **z; }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { int x; int *y; x = 3; y = &x; *y = 5; return x; }"),
+            5
+        );
+        assert_eq!(
+            run("int main() { int x, *y, **z; x = 3; y = &x; z = &y; **z = 7; return x; }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { int x; x = 1; set(&x, 9); return x; } int set(int *p, int v) { *p = v; }"),
+            9
+        );
+    }
+
+    #[test]
+    fn test_pointer_arithmetic() {
+        // 別々の変数の並び方には依存しないよう、一つの配列の要素どうしで計算する
+        assert_eq!(
+            run("int main() { int a[2]; a[0] = 5; a[1] = 3; return *(&a[0] + 1); }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { int a[2]; a[0] = 5; a[1] = 3; return *(1 + &a[0]); }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { int a[2]; a[0] = 5; a[1] = 3; return *(&a[1] - 1); }"),
+            5
+        );
+        assert_eq!(
+            run("int main() { int a[2]; a[0] = 5; a[1] = 3; *(&a[0] + 1) = 7; return a[1]; }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { int a[2], *p; p = &a[0]; p = p + 1; return p - &a[0]; }"),
+            1
+        );
+        assert_eq!(run("int main() { int a[4]; return &a[3] - &a[1]; }"), 2);
+    }
+
+    #[test]
+    fn test_sizeof() {
+        assert_eq!(run("int main() { int x; return sizeof(x); }"), 4);
+        assert_eq!(run("int main() { int x; return sizeof x; }"), 4);
+        assert_eq!(run("int main() { int *x; return sizeof(x); }"), 8);
+        assert_eq!(run("int main() { int x; return sizeof(x + 3); }"), 4);
+        assert_eq!(run("int main() { int *x; return sizeof(*x); }"), 4);
+        assert_eq!(run("int main() { return sizeof(1); }"), 4);
+        // sizeof の結果は size_t (unsigned long) 型
+        assert_eq!(run("int main() { return sizeof sizeof 1; }"), 8);
+        assert_eq!(run("int main() { return sizeof(sizeof(int)); }"), 8);
+        assert_eq!(run("int main() { return sizeof(int) - 5 > 0; }"), 1);
+        assert_eq!(run("int main() { return (sizeof(int) - 5) >> 63; }"), 1);
+        assert_eq!(run("int main() { return sizeof(int); }"), 4);
+        assert_eq!(run("int main() { return sizeof(int **); }"), 8);
+        assert_eq!(
+            run("int main() { int x; x = 1; sizeof(x = 5); return x; }"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_array() {
+        assert_eq!(
+            run("int main() { int x[2]; int *y; y = x; *y = 3; return *x; }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { int x[3]; *x = 3; *(x + 1) = 4; *(x + 2) = 5; return *x + *(x + 2); }"),
+            8
+        );
+        assert_eq!(
+            run("int main() { int x[2][3]; int *y; y = x; *(y + 5) = 6; return **(x + 1) + *(*(x + 1) + 2); }"),
+            6
+        );
+        assert_eq!(run("int main() { int x[3]; return sizeof(x); }"), 12);
+        assert_eq!(run("int main() { int x[3][4]; return sizeof(x); }"), 48);
+        assert_eq!(run("int main() { int x[3][4]; return sizeof(*x); }"), 16);
+        assert_eq!(run("int main() { int x[3][4]; return sizeof(**x); }"), 4);
+        assert_eq!(run("int main() { int x[3]; return sizeof(x + 1); }"), 8);
+        assert_eq!(run("int main() { return sizeof(int[3][4]); }"), 48);
+        assert_eq!(run("int main() { return sizeof(int (*)[4]); }"), 8);
+    }
+
+    #[test]
+    fn test_array_subscript() {
+        assert_eq!(
+            run("int main() { int x[3]; x[0] = 3; x[1] = 4; x[2] = 5; return x[0] + x[2]; }"),
+            8
+        );
+        assert_eq!(
+            run("int main() { int x[3]; x[0] = 3; 1[x] = 4; return x[0] * x[1]; }"),
+            12
+        );
+        assert_eq!(
+            run("
+                int main() {
+                    int x[2][3], i, j;
+                    for (i = 0; i < 2; i = i + 1)
+                        for (j = 0; j < 3; j = j + 1)
+                            x[i][j] = i * 3 + j;
+                    return x[1][2] * 10 + x[0][1];
+                }
+            "),
+            51
+        );
+        assert_eq!(
+            run("int main() { int x[2][3], (*p)[3]; p = x; p[1][1] = 7; return x[1][1]; }"),
+            7
+        );
+        assert_eq!(
+            run("
+                int main() { int x[4]; x[3] = 9; return last(x, 4); }
+                int last(int a[4], int n) { return a[n - 1]; }
+            "),
+            9
+        );
+    }
+
+    #[test]
+    fn test_global_variable() {
+        assert_eq!(run("int x; int main() { return x; }"), 0);
+        assert_eq!(run("int x; int main() { x = 3; return x; }"), 3);
+        assert_eq!(
+            run("int x, y; int main() { x = 3; y = 4; return x + y; }"),
+            7
+        );
+        assert_eq!(
+            run("int x[4]; int main() { x[0] = 0; x[1] = 1; x[2] = 2; x[3] = 3; return x[3]; }"),
+            3
+        );
+        assert_eq!(run("int x; int main() { return sizeof(x); }"), 4);
+        assert_eq!(run("int x[4]; int main() { return sizeof(x); }"), 16);
+        assert_eq!(
+            run("int x; int main() { int x; x = 5; set(); return x; } int set() { x = 7; }"),
+            5
+        );
+        assert_eq!(
+            run("int counter; int main() { inc(); inc(); inc(); return counter; } int inc() { counter = counter + 1; }"),
+            3
+        );
+        assert_eq!(
+            run("int add(int x, int y); int main() { return add(2, 3); } int add(int x, int y) { return x + y; }"),
+            5
+        );
+    }
+
+    #[test]
+    fn test_char() {
+        assert_eq!(run("int main() { char x; x = 1; return x; }"), 1);
+        assert_eq!(
+            run("int main() { char x; char y; x = 1; y = 2; return y; }"),
+            2
+        );
+        assert_eq!(run("char x; int main() { x = 1; return x; }"), 1);
+        assert_eq!(run("int main() { char x; return sizeof(x); }"), 1);
+        assert_eq!(run("int main() { char x[10]; return sizeof(x); }"), 10);
+        assert_eq!(run("int main() { char x; x = 257; return x; }"), 1);
+        assert_eq!(run("int main() { char x; x = 255; return x == -1; }"), 1);
+        assert_eq!(
+            run("int main() { char x[3]; int y; x[0] = -1; x[1] = 2; y = 4; return x[0] + x[1] + y; }"),
+            5
+        );
+        assert_eq!(
+            run("int main() { return sub_char(7, 3, 3); } int sub_char(char a, char b, char c) { return a - b - c; }"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_string_literal() {
+        assert_eq!(run(r#"int main() { return "abc"[0]; }"#), 97);
+        assert_eq!(run(r#"int main() { return "abc"[1]; }"#), 98);
+        assert_eq!(run(r#"int main() { return "abc"[3]; }"#), 0);
+        assert_eq!(run(r#"int main() { return sizeof("abc"); }"#), 4);
+        assert_eq!(
+            run(r#"int main() { char *p; p = "xyz"; return p[2]; }"#),
+            122
+        );
+        assert_eq!(run(r#"int main() { return "\a\b\t\n\v\f\r\e"[7]; }"#), 27);
+        assert_eq!(run(r#"int main() { return "\x41\101\j"[2]; }"#), 106);
+        assert_eq!(run(r#"int main() { return "\0"[0] + sizeof("\0"); }"#), 2);
+        assert_eq!(run(r#"int main() { return "\777"[0]; }"#), 255);
+        assert_eq!(run(r#"int main() { return sizeof("\x41\x42"); }"#), 3);
+        assert_eq!(run(r#"int main() { return strlen("hello, world"); }"#), 12);
+        assert_eq!(
+            run_with_object(
+                r#"int main() { return check("hello\n"); }"#,
+                Some(r#"int strcmp(); int check(char *s) { return strcmp(s, "hello\n") == 0; }"#)
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_eq!(
+            run("
+                // line comment
+                int main() {
+                    /* block
+                       comment */
+                    return 2; // trailing comment
+                }
+            "),
+            2
+        );
+    }
+
+    #[test]
+    fn test_struct() {
+        assert_eq!(
+            run("int main() { struct { int a; int b; } x; x.a = 1; x.b = 2; return x.a; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { struct { int a; int b; } x; x.a = 1; x.b = 2; return x.b; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { struct { char a; int b; char c; } x; x.a = 1; x.b = 2; x.c = 3; return x.c; }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { struct { int a; int b; } x[3]; int *p; p = x; p[0] = 0; x[0].a = 5; x[2].b = 7; return x[0].a + p[5]; }"),
+            12
+        );
+        assert_eq!(
+            run("int main() { struct { struct { char b; } a; } x; x.a.b = 6; return x.a.b; }"),
+            6
+        );
+        assert_eq!(
+            run("int main() { struct { int a[3]; int b[5]; } x; int *p; p = &x; x.b[0] = 7; return p[3]; }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { struct t { int a; } x; struct t *p; p = &x; p->a = 3; return x.a; }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { struct t { int a; } x; struct t *p; p = &x; x.a = 4; return (*p).a; }"),
+            4
+        );
+    }
+
+    #[test]
+    fn test_struct_layout() {
+        assert_eq!(
+            run("int main() { struct { int a; } x; return sizeof(x); }"),
+            4
+        );
+        assert_eq!(
+            run("int main() { struct { char a; int b; } x; return sizeof(x); }"),
+            8
+        );
+        assert_eq!(
+            run("int main() { struct { int a; char b; } x; return sizeof(x); }"),
+            8
+        );
+        assert_eq!(
+            run("int main() { struct { char a; char b; } x; return sizeof(x); }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { struct { char a[3]; } x[2]; return sizeof(x); }"),
+            6
+        );
+        assert_eq!(run("int main() { struct {} x; return sizeof(x); }"), 0);
+        assert_eq!(
+            run("int main() { struct { char a; int b; } x; return &x.b - &x.a; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { struct { char a; int b; } x; char *p, *q; p = &x.a; q = &x.b; return q - p; }"),
+            4
+        );
+        assert_eq!(
+            run("int main() { struct t { char a[2]; }; return sizeof(struct t); }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { struct t { char a; }; { struct t { int a; }; } return sizeof(struct t); }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { struct t { int x; }; int t; t = 1; struct t y; y.x = 2; return t + y.x; }"),
+            3
+        );
+    }
+
+    #[test]
+    fn test_union() {
+        assert_eq!(
+            run("int main() { union { int a; char b[6]; } x; return sizeof(x); }"),
+            8
+        );
+        assert_eq!(
+            run("int main() { union { char a; char b[6]; } x; return sizeof(x); }"),
+            6
+        );
+        assert_eq!(
+            run("int main() { union { int a; char b[4]; } x; x.a = 515; return x.b[0]; }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { union { int a; char b[4]; } x; x.a = 515; return x.b[1]; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { union { struct { char a; char b; } c; int d; } x; x.d = 258; return x.c.b; }"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_struct_assign() {
+        assert_eq!(
+            run("int main() { struct { int a, b; } x, y; x.a = 3; x.b = 5; y = x; return y.a + y.b; }"),
+            8
+        );
+        assert_eq!(
+            run("int main() { struct t { char a, b; } x, y; x.a = 5; y = x; return y.a; }"),
+            5
+        );
+        assert_eq!(
+            run("int main() { union { int a, b; } x, y; x.a = 7; y = x; return y.b; }"),
+            7
+        );
+    }
+
+    #[test]
+    fn test_struct_tag_and_pointer() {
+        assert_eq!(
+            run("
+                struct node { int value; struct node *next; };
+                int main() {
+                    struct node a, b, c, *p;
+                    int sum;
+                    a.value = 1; a.next = &b;
+                    b.value = 2; b.next = &c;
+                    c.value = 3; c.next = 0;
+                    sum = 0;
+                    for (p = &a; p; p = p->next)
+                        sum = sum + p->value;
+                    return sum;
+                }
+            "),
+            6
+        );
+        assert_eq!(
+            run("
+                struct point { int x; int y; } origin;
+                struct point *get() { return &origin; }
+                int main() { get()->y = 4; return origin.y; }
+            "),
+            4
+        );
+        assert_eq!(
+            run("struct t; struct t { int a, b, c; }; int main() { return sizeof(struct t); }"),
+            12
+        );
+    }
+
+    #[test]
+    fn test_enum() {
+        assert_eq!(
+            run("int main() { enum { zero, one, two }; return zero; }"),
+            0
+        );
+        assert_eq!(
+            run("int main() { enum { zero, one, two }; return one; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { enum { zero, one, two }; return two; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { enum { five = 5, six, seven }; return seven; }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { enum { zero, five = 5, three = 3, four }; return four; }"),
+            4
+        );
+        assert_eq!(
+            run("int main() { enum { zero, one, } x; x = one; return x; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { enum t { zero, one, two }; enum t y; y = two; return y; }"),
+            2
+        );
+        assert_eq!(run("int main() { enum { a = -1, b }; return b; }"), 0);
+        assert_eq!(
+            run(
+                "int main() { enum { n = 2 * 3 + 1 }; int x[n]; return sizeof(x) / sizeof(x[0]); }"
+            ),
+            7
+        );
+        assert_eq!(
+            run("enum color { red, green, blue }; int main() { enum color c; c = blue; return c * 10 + green; }"),
+            21
+        );
+    }
+
+    #[test]
+    fn test_typedef() {
+        assert_eq!(
+            run("int main() { typedef int t; t x; x = 1; return x; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { typedef struct { int a; } t; t x; x.a = 1; return x.a; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { typedef int t; t t; t = 1; return t; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { typedef struct { int a; } t; { typedef int t; } t x; x.a = 2; return x.a; }"),
+            2
+        );
+        assert_eq!(
+            run("typedef int t; t x; int main() { x = 3; return x; }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { typedef int *p, a[3]; a x; return sizeof(x) + sizeof(p); }"),
+            20
+        );
+        assert_eq!(run("typedef char c; int main() { return sizeof(c); }"), 1);
+        assert_eq!(
+            run("
+                typedef struct node node;
+                struct node { int value; node *next; };
+                int main() { node a, b; a.next = &b; b.value = 5; return a.next->value; }
+            "),
+            5
+        );
+        assert_eq!(
+            run("typedef int t; int main() { int t; t = 4; return t; }"),
+            4
+        );
+    }
+
+    #[test]
+    fn test_do_while() {
+        assert_eq!(
+            run("int main() { int i; i = 0; do i = i + 1; while (i < 10); return i; }"),
+            10
+        );
+        assert_eq!(
+            run("int main() { int i; i = 5; do { i = i + 1; } while (0); return i; }"),
+            6
+        );
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        assert_eq!(
+            run(
+                "int main() { int i; i = 0; for (;;) { i = i + 1; if (i == 3) break; } return i; }"
+            ),
+            3
+        );
+        assert_eq!(
+            run("int main() { int i; i = 0; while (1) { if (i == 4) break; i = i + 1; } return i; }"),
+            4
+        );
+        assert_eq!(
+            run("int main() { int i; i = 0; do { i = i + 1; if (i == 5) break; } while (1); return i; }"),
+            5
+        );
+        assert_eq!(
+            run("int main() { int i, j; j = 0; for (i = 0; i < 10; i = i + 1) { if (i < 5) continue; j = j + i; } return j; }"),
+            35
+        );
+        assert_eq!(
+            run("int main() { int i, j; i = 0; j = 0; while (i < 10) { i = i + 1; if (i > 5) continue; j = j + 1; } return j; }"),
+            5
+        );
+        assert_eq!(
+            run("int main() { int i; i = 0; do { i = i + 1; continue; i = 100; } while (i < 7); return i; }"),
+            7
+        );
+        assert_eq!(
+            run("
+                int main() {
+                    int i, j, n;
+                    n = 0;
+                    for (i = 0; i < 5; i = i + 1) {
+                        for (j = 0; j < 5; j = j + 1) {
+                            if (j == 2) break;
+                            n = n + 1;
+                        }
+                        if (i == 3) break;
+                    }
+                    return n;
+                }
+            "),
+            8
+        );
+    }
+
+    #[test]
+    fn test_switch() {
+        let program = "
+            int f(int x) {
+                int r;
+                r = 0;
+                switch (x) {
+                case 0: r = 5; break;
+                case 1: r = 6; break;
+                case 2: r = 7; break;
+                }
+                return r;
+            }
+        ";
+        assert_eq!(run(&format!("{program} int main() {{ return f(0); }}")), 5);
+        assert_eq!(run(&format!("{program} int main() {{ return f(1); }}")), 6);
+        assert_eq!(run(&format!("{program} int main() {{ return f(2); }}")), 7);
+        assert_eq!(run(&format!("{program} int main() {{ return f(3); }}")), 0);
+
+        assert_eq!(
+            run("int main() { int i; i = 0; switch (3) { case 0: i = 5; break; default: i = 7; } return i; }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { int i; i = 0; switch (0) { case 0: i = i + 1; case 1: i = i + 2; break; case 2: i = i + 4; } return i; }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { int i; i = 0; switch (1) { default: i = 9; break; case 1: i = 1; } return i; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { int i; i = 0; switch (-1) { case 255: i = 3; break; case -1: i = 2; } return i; }"),
+            2
+        );
+        assert_eq!(
+            run("
+                int main() {
+                    int i, n;
+                    n = 0;
+                    for (i = 0; i < 4; i = i + 1) {
+                        switch (i) {
+                        case 1: continue;
+                        case 2: n = n + 10; break;
+                        default: n = n + 1;
+                        }
+                        n = n + 20;
+                    }
+                    return n;
+                }
+            "),
+            72
+        );
+        assert_eq!(
+            run("int main() { enum { A, B }; switch (B) { case A: return 1; case B: switch (A) { case B: return 2; } return 3; } return 4; }"),
+            3
+        );
+        // case の値は整数拡張した制御式の型で比較される
+        assert_eq!(
+            run(
+                "int main() { char c = 44; switch (c) { case 300: return 1; default: return 2; } }"
+            ),
+            2
+        );
+        assert_eq!(
+            run("int main() { unsigned char c = 255; switch (c) { case -1: return 1; case 255: return 2; } return 3; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { long x; x = 5000000000; switch (x) { case 5000000000: return 1; default: return 2; } }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { long x; x = 4294967296; switch (x) { case 0: return 1; case 4294967296: return 2; } return 3; }"),
+            2
+        );
+    }
+
+    #[test]
+    fn test_goto() {
+        assert_eq!(
+            run("int main() { int i; i = 0; goto a; a: i = i + 1; b: i = i + 1; c: i = i + 1; return i; }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { int i; i = 0; goto e; d: i = i + 1; e: i = i + 1; f: i = i + 1; return i; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { int i; i = 0; loop: i = i + 1; if (i < 6) goto loop; return i; }"),
+            6
+        );
+        assert_eq!(
+            run("int main() { return f(); } int f() { goto end; return 1; end: return 2; } int g() { end: return 3; }"),
+            2
+        );
+        assert_eq!(run("typedef int t; int main() { t: return 4; }"), 4);
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        assert_eq!(run("int main() { return !1; }"), 0);
+        assert_eq!(run("int main() { return !0; }"), 1);
+        assert_eq!(run("int main() { return !!5; }"), 1);
+        assert_eq!(run("int main() { return 1 && 5; }"), 1);
+        assert_eq!(run("int main() { return 2 && 0; }"), 0);
+        assert_eq!(run("int main() { return 0 && 2; }"), 0);
+        assert_eq!(run("int main() { return 1 || 0; }"), 1);
+        assert_eq!(run("int main() { return 0 || 3; }"), 1);
+        assert_eq!(run("int main() { return 0 || 0; }"), 0);
+        assert_eq!(run("int main() { return 0 || 1 && 0; }"), 0);
+        assert_eq!(run("int main() { return 1 == 1 && 2 < 3; }"), 1);
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        assert_eq!(
+            run("int main() { int x; x = 1; 0 && (x = 2); return x; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { int x; x = 1; 1 && (x = 2); return x; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { int x; x = 1; 1 || (x = 2); return x; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { int x; x = 1; 0 || (x = 2); return x; }"),
+            2
+        );
+        assert_eq!(
+            run("struct t { int v; }; int main() { struct t *p; p = 0; return p && p->v; }"),
+            0
+        );
+    }
+
+    #[test]
+    fn test_conditional_operator() {
+        assert_eq!(run("int main() { return 1 ? 2 : 3; }"), 2);
+        assert_eq!(run("int main() { return 0 ? 2 : 3; }"), 3);
+        assert_eq!(run("int main() { return 0 ? 1 : 0 ? 2 : 4; }"), 4);
+        assert_eq!(
+            run("int main() { int x; x = 0; 1 ? (x = 5) : (x = 6); return x; }"),
+            5
+        );
+        assert_eq!(
+            run("int main() { int x; x = 1 ? 0 ? 7 : 8 : 9; return x; }"),
+            8
+        );
+        assert_eq!(run("int main() { enum { n = 1 ? 3 : 4 }; return n; }"), 3);
+        assert_eq!(
+            run("int main() { int a[2], *p; a[1] = 6; p = 1 ? a : 0; return p[1]; }"),
+            6
+        );
+    }
+
+    #[test]
+    fn test_comma_operator() {
+        assert_eq!(run("int main() { return (3, 5); }"), 5);
+        assert_eq!(run("int main() { int i; return (i = 2, i + 3); }"), 5);
+        assert_eq!(run("int main() { int i, j; (i = 5, j) = 6; return j; }"), 6);
+        assert_eq!(
+            run("int main() { int i, j, n; n = 0; for (i = 0, j = 10; i < j; i = i + 1, j = j - 1) n = n + 1; return n; }"),
+            5
+        );
+    }
+
+    #[test]
+    fn test_remainder() {
+        assert_eq!(run("int main() { return 17 % 5; }"), 2);
+        assert_eq!(run("int main() { return 10 % 2; }"), 0);
+        assert_eq!(run("int main() { return -7 % 3 + 5; }"), 4);
+        assert_eq!(run("int main() { return 3 + 10 % 4 * 2; }"), 7);
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        assert_eq!(run("int main() { return 12 & 10; }"), 8);
+        assert_eq!(run("int main() { return 12 | 3; }"), 15);
+        assert_eq!(run("int main() { return 12 ^ 10; }"), 6);
+        assert_eq!(run("int main() { return ~0 + 1; }"), 0);
+        assert_eq!(run("int main() { return ~-3; }"), 2);
+        // & は == より、^ は & より、| は ^ より優先順位が低い
+        assert_eq!(run("int main() { return 3 & 2 == 2; }"), 1);
+        assert_eq!(run("int main() { return 1 | 6 ^ 3 & 2; }"), 5);
+        assert_eq!(run("int main() { return 1 && 2 | 0; }"), 1);
+    }
+
+    #[test]
+    fn test_shift_operators() {
+        assert_eq!(run("int main() { return 1 << 4; }"), 16);
+        assert_eq!(run("int main() { return 256 >> 3; }"), 32);
+        assert_eq!(run("int main() { return -16 >> 2 == -4; }"), 1);
+        assert_eq!(run("int main() { return 1 << 2 + 1; }"), 8);
+        assert_eq!(run("int main() { return 1 << 3 < 9; }"), 1);
+        assert_eq!(run("int main() { enum { n = 1 << 5 | 3 }; return n; }"), 35);
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        assert_eq!(run("int main() { int i; i = 2; i += 5; return i; }"), 7);
+        assert_eq!(run("int main() { int i; i = 2; return i += 5; }"), 7);
+        assert_eq!(run("int main() { int i; i = 5; i -= 2; return i; }"), 3);
+        assert_eq!(run("int main() { int i; i = 3; i *= 2; return i; }"), 6);
+        assert_eq!(run("int main() { int i; i = 6; i /= 2; return i; }"), 3);
+        assert_eq!(run("int main() { int i; i = 10; i %= 4; return i; }"), 2);
+        assert_eq!(run("int main() { int i; i = 6; i &= 3; return i; }"), 2);
+        assert_eq!(run("int main() { int i; i = 6; i |= 3; return i; }"), 7);
+        assert_eq!(run("int main() { int i; i = 6; i ^= 3; return i; }"), 5);
+        assert_eq!(run("int main() { int i; i = 1; i <<= 4; return i; }"), 16);
+        assert_eq!(run("int main() { int i; i = 64; i >>= 3; return i; }"), 8);
+        assert_eq!(
+            run("int main() { int i, j; i = j = 1; i += j += 2; return i * 10 + j; }"),
+            43
+        );
+        assert_eq!(
+            run("int main() { int a[3], *p; a[0] = 1; a[1] = 2; a[2] = 3; p = a; p += 2; return *p; }"),
+            3
+        );
+        assert_eq!(
+            run("struct t { int a; char b; }; int main() { struct t s; s.a = 1; s.b = 2; s.a += 4; s.b *= 3; return s.a + s.b; }"),
+            11
+        );
+    }
+
+    #[test]
+    fn test_compound_assignment_evaluates_lvalue_once() {
+        assert_eq!(
+            run("int main() { int a[3], i; a[0] = 0; a[1] = 10; a[2] = 20; i = 0; a[++i] += 5; return a[1] + i; }"),
+            16
+        );
+    }
+
+    #[test]
+    fn test_increment_decrement() {
+        assert_eq!(run("int main() { int i; i = 2; return ++i; }"), 3);
+        assert_eq!(run("int main() { int i; i = 2; return --i; }"), 1);
+        assert_eq!(run("int main() { int i; i = 2; return i++; }"), 2);
+        assert_eq!(run("int main() { int i; i = 2; return i--; }"), 2);
+        assert_eq!(run("int main() { int i; i = 2; i++; return i; }"), 3);
+        assert_eq!(run("int main() { int i; i = 2; i--; return i; }"), 1);
+        assert_eq!(
+            run("int main() { int i, n; n = 0; for (i = 0; i < 10; i++) n += i; return n; }"),
+            45
+        );
+        assert_eq!(
+            run("int main() { int a[3], *p, x; a[0] = 1; a[1] = 2; a[2] = 3; p = a; p++; x = *p++; return x + *p; }"),
+            5
+        );
+        assert_eq!(
+            run("int main() { int a[3], *p; a[0] = 1; a[1] = 2; a[2] = 3; p = a + 2; --p; return *p--; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { int a[2], *p; a[0] = 4; a[1] = 7; p = a; (*p)++; return a[0]; }"),
+            5
+        );
+    }
+
+    #[test]
+    fn test_integer_types() {
+        assert_eq!(run("int main() { return sizeof(char); }"), 1);
+        assert_eq!(run("int main() { return sizeof(short); }"), 2);
+        assert_eq!(run("int main() { return sizeof(short int); }"), 2);
+        assert_eq!(run("int main() { return sizeof(int short); }"), 2);
+        assert_eq!(run("int main() { return sizeof(int); }"), 4);
+        assert_eq!(run("int main() { return sizeof(long); }"), 8);
+        assert_eq!(run("int main() { return sizeof(long int); }"), 8);
+        assert_eq!(run("int main() { return sizeof(long long); }"), 8);
+        assert_eq!(run("int main() { return sizeof(signed char); }"), 1);
+        assert_eq!(run("int main() { return sizeof(unsigned char); }"), 1);
+        assert_eq!(run("int main() { return sizeof(unsigned short); }"), 2);
+        assert_eq!(run("int main() { return sizeof(unsigned); }"), 4);
+        assert_eq!(run("int main() { return sizeof(signed); }"), 4);
+        assert_eq!(
+            run("int main() { return sizeof(unsigned long long int); }"),
+            8
+        );
+        assert_eq!(
+            run("int main() { struct { char a; short b; } x; return sizeof(x); }"),
+            4
+        );
+        assert_eq!(
+            run("int main() { struct { char a; long b; } x; return sizeof(x); }"),
+            16
+        );
+        assert_eq!(
+            run("int main() { long x; short y; x = 5; y = 3; return x - y; }"),
+            2
+        );
+    }
+
+    #[test]
+    fn test_sized_load_and_store() {
+        assert_eq!(run("int main() { char c; c = 255; return c == -1; }"), 1);
+        assert_eq!(
+            run("int main() { unsigned char c; c = 255; return c == 255; }"),
+            1
+        );
+        assert_eq!(run("int main() { short s; s = 65535; return s == -1; }"), 1);
+        assert_eq!(
+            run("int main() { unsigned short s; s = 65539; return s; }"),
+            3
+        );
+        assert_eq!(
+            run("int main() { int a[2]; a[0] = -1; a[1] = 2; return a[1]; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { union { long l; int i[2]; } u; u.l = 1; u.l = u.l << 33 | 1; return u.i[0] + u.i[1]; }"),
+            3
+        );
+    }
+
+    #[test]
+    fn test_usual_arithmetic_conversions() {
+        assert_eq!(
+            run("int main() { long x; x = 2147483647; x = x + 1; return x > 0; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { long x; int i; i = 1; x = (long)i << 40; return x >> 38; }"),
+            4
+        );
+        assert_eq!(
+            run("int main() { long x; x = 1; x = x << 40; return x >> 38; }"),
+            4
+        );
+        assert_eq!(
+            run("int main() { unsigned x; x = 0; x = x - 1; return x > 0; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { int x; unsigned y; x = -1; y = 1; return x < y; }"),
+            0
+        );
+        assert_eq!(
+            run("int main() { int x; long y; x = -1; y = 1; return x < y; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { unsigned char c; c = 200; return c + c > 255; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { char c; c = 127; c++; return c == -128; }"),
+            1
+        );
+        assert_eq!(run("int main() { char c; c = 127; return c++; }"), 127);
+    }
+
+    #[test]
+    fn test_unsigned_arithmetic() {
+        assert_eq!(
+            run("int main() { unsigned x; x = -1; return x / 16777216; }"),
+            255
+        );
+        assert_eq!(run("int main() { unsigned x; x = -1; return x % 10; }"), 5);
+        assert_eq!(run("int main() { int x; x = -7; return x / 2 == -3; }"), 1);
+        assert_eq!(run("int main() { int x; x = -7; return x % 2 == -1; }"), 1);
+        assert_eq!(run("int main() { long x; x = -7; return x / 2 == -3; }"), 1);
+        assert_eq!(
+            run("int main() { unsigned x; x = -8; return x >> 28; }"),
+            15
+        );
+        assert_eq!(
+            run("int main() { int x; x = -8; return x >> 28 == -1; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { unsigned long x; x = -1; return x >> 60; }"),
+            15
+        );
+        assert_eq!(
+            run("int main() { unsigned x, y; x = 3; y = 5; return x - y > 0; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { unsigned x; x = 2000000000; x = x + x; return x > 2000000000; }"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_integer_parameters() {
+        assert_eq!(
+            run("long add(long a, long b) { return a + b; } int main() { return add(1, 2); }"),
+            3
+        );
+        assert_eq!(
+            run("char f(int x) { return x; } int main() { return f(257); }"),
+            1
+        );
+        assert_eq!(
+            run("int f(short a, unsigned char b) { return a + b; } int main() { return f(-1, 511); }"),
+            254
+        );
+        assert_eq!(
+            run("long f(long a, long b, long c, long d, long e, long g, int h) { return h; } int main() { return f(1, 2, 3, 4, 5, 6, -1) == -1; }"),
+            1
+        );
+
+        let object = "
+            long twice(long x) { return x * 2; }
+            unsigned char low(int x) { return x; }
+        ";
+        assert_eq!(
+            run_with_object(
+                "long twice(long x); int main() { int a; a = -3; return twice(a) == -6; }",
+                Some(object)
+            ),
+            1
+        );
+        assert_eq!(
+            run_with_object(
+                "unsigned char low(int x); int main() { return low(513) == 1; }",
+                Some(object)
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn test_integer_literals() {
+        assert_eq!(run("int main() { return 0x10; }"), 16);
+        assert_eq!(run("int main() { return 0XfF; }"), 255);
+        assert_eq!(run("int main() { return 017; }"), 15);
+        assert_eq!(run("int main() { return 0; }"), 0);
+        assert_eq!(run("int main() { return 0b101; }"), 5);
+        assert_eq!(run("int main() { return 0B11; }"), 3);
+        assert_eq!(run("int main() { return 10u + 5U; }"), 15);
+        assert_eq!(run("int main() { return 3l + 4LL + 5ul + 6LLU; }"), 18);
+        assert_eq!(
+            run("int main() { long x; x = 4294967296; return x >> 32; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { long x; x = 0x123456789abcdef0; return x >> 60; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { long x; x = -9223372036854775807 - 1; return x < 0; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { unsigned long x; x = 0xffffffffffffffff; return x >> 60; }"),
+            15
+        );
+    }
+
+    #[test]
+    fn test_integer_literal_types() {
+        assert_eq!(run("int main() { return sizeof(1); }"), 4);
+        assert_eq!(run("int main() { return sizeof(1u); }"), 4);
+        assert_eq!(run("int main() { return sizeof(1L); }"), 8);
+        assert_eq!(run("int main() { return sizeof(1LL); }"), 8);
+        assert_eq!(run("int main() { return sizeof(1uL); }"), 8);
+        assert_eq!(run("int main() { return sizeof(2147483647); }"), 4);
+        assert_eq!(run("int main() { return sizeof(2147483648); }"), 8);
+        assert_eq!(run("int main() { return sizeof(0x7fffffff); }"), 4);
+        assert_eq!(run("int main() { return sizeof(0xffffffff); }"), 4);
+        assert_eq!(run("int main() { return sizeof(0x100000000); }"), 8);
+        // 16進数のリテラルは値によって符号なしの型になる
+        assert_eq!(run("int main() { return 0xffffffff > 0; }"), 1);
+        assert_eq!(run("int main() { return 4294967295 > 0; }"), 1);
+        assert_eq!(run("int main() { return -1 < 0u; }"), 0);
+        assert_eq!(run("int main() { return -1 < 0; }"), 1);
+        assert_eq!(run("int main() { return 0xffffffffu >> 31; }"), 1);
+        // long に収まらない10進数のリテラルは unsigned long になる
+        assert_eq!(run("int main() { return 9223372036854775808 > 0; }"), 1);
+        assert_eq!(run("int main() { return 18446744073709551615 >> 63; }"), 1);
+        assert_eq!(
+            run("int main() { enum { n = 0xffffffff / 2 > 0 }; return n; }"),
+            1
+        );
+        assert_eq!(run("int main() { enum { n = -1 < 0u }; return n; }"), 0);
+    }
+
+    #[test]
+    fn test_char_literals() {
+        assert_eq!(run("int main() { return 'a'; }"), 97);
+        assert_eq!(run("int main() { return '\\n'; }"), 10);
+        assert_eq!(run("int main() { return '\\0'; }"), 0);
+        assert_eq!(run("int main() { return '\\x41'; }"), 65);
+        assert_eq!(run("int main() { return '\\''; }"), 39);
+        assert_eq!(run("int main() { return '\"'; }"), 34);
+        assert_eq!(run("int main() { return '\\xff' == -1; }"), 1);
+        assert_eq!(run("int main() { return sizeof('a'); }"), 4);
+        assert_eq!(
+            run("int main() { char *s; s = \"abc\"; return s[1] == 'b'; }"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        assert_eq!(run("int main() { return (char)8590066177; }"), 1);
+        assert_eq!(run("int main() { return (short)65537; }"), 1);
+        assert_eq!(run("int main() { return (int)8589934593; }"), 1);
+        assert_eq!(run("int main() { return (long)-1 < 0; }"), 1);
+        assert_eq!(run("int main() { return (unsigned char)-1; }"), 255);
+        assert_eq!(run("int main() { return (unsigned short)-1 == 65535; }"), 1);
+        assert_eq!(run("int main() { return (unsigned)-1 > 0; }"), 1);
+        assert_eq!(run("int main() { return (char)255 == -1; }"), 1);
+        assert_eq!(run("int main() { return (signed char)255 == -1; }"), 1);
+        assert_eq!(run("int main() { return (long)(int)4294967295 == -1; }"), 1);
+        assert_eq!(
+            run("int main() { return (long)(unsigned)-1 == 4294967295; }"),
+            1
+        );
+        assert_eq!(run("int main() { return sizeof((char)1); }"), 1);
+        assert_eq!(run("int main() { return sizeof((long)1); }"), 8);
+        assert_eq!(run("int main() { return -(char)1 + 3; }"), 2);
+        assert_eq!(run("int main() { return (int)(char)300 * 2; }"), 88);
+        assert_eq!(run("int main() { enum { n = (char)257 }; return n; }"), 1);
+    }
+
+    #[test]
+    fn test_pointer_cast() {
+        assert_eq!(
+            run("int main() { int x; char *p; x = 513; p = (char *)&x; return p[1]; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { int a[2]; long p; a[1] = 7; p = (long)a; return *(int *)(p + 4); }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { int x; x = 3; *(char *)&x = 5; return x; }"),
+            5
+        );
+        assert_eq!(
+            run("typedef int *ip; int main() { int x; x = 9; return *(ip)&x; }"),
+            9
+        );
+    }
+
+    #[test]
+    fn test_void() {
+        assert_eq!(run("int main() { int x; x = 3; (void)x; return x; }"), 3);
+        assert_eq!(run("int main() { (void)5; return 1; }"), 1);
+        assert_eq!(
+            run("int x; void set(int v) { x = v; return; } int main() { set(4); return x; }"),
+            4
+        );
+        assert_eq!(
+            run("void nop(void) {} int main(void) { nop(); return 2; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { int x; void *p; x = 6; p = &x; return *(int *)p; }"),
+            6
+        );
+        assert_eq!(
+            run("int main() { void *p; p = 0; return (long)(p + 3); }"),
+            3
+        );
+        assert_eq!(run("int main() { return sizeof(void *); }"), 8);
+    }
+
+    #[test]
+    fn test_float_arithmetic() {
+        assert_eq!(run("int main() { return 1.5 + 2.5; }"), 4);
+        assert_eq!(run("int main() { return 7.5 - 2.25 * 2; }"), 3);
+        assert_eq!(run("int main() { return 10.0 / 4 * 2; }"), 5);
+        assert_eq!(run("int main() { double x; x = 3; return x / 2 * 4; }"), 6);
+        assert_eq!(run("int main() { float x; x = 0.1f; return x * 30; }"), 3);
+        assert_eq!(run("int main() { double x; x = -2.5; return -x * 2; }"), 5);
+        // 単項マイナスは符号ビットを反転するので -0.0 になる
+        assert_eq!(
+            run("int main() { double x = 0.0; return 1 / (-x) < 0; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { float x = 0.0f; return 1 / (-x) < 0; }"),
+            1
+        );
+        assert_eq!(run("double x = -0.0; int main() { return 1 / x < 0; }"), 1);
+        assert_eq!(run("int main() { int x = 3; return -x + 5; }"), 2);
+        assert_eq!(
+            run("int main() { double x; x = 1; x += 0.5; x *= 4; return x; }"),
+            6
+        );
+        assert_eq!(
+            run("int main() { double x; x = 1.5; x++; ++x; return x * 2; }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { return sizeof(float) + sizeof(double); }"),
+            12
+        );
+        assert_eq!(run("int main() { return sizeof(1.0f) + sizeof(1.0); }"), 12);
+    }
+
+    #[test]
+    fn test_float_comparison() {
+        assert_eq!(run("int main() { return 0.5 < 1.0; }"), 1);
+        assert_eq!(run("int main() { return 1.0 < 0.5; }"), 0);
+        assert_eq!(run("int main() { return 1.0 <= 1.0; }"), 1);
+        assert_eq!(run("int main() { return 2.0 > 1; }"), 1);
+        assert_eq!(run("int main() { return 1.5 == 1.5; }"), 1);
+        assert_eq!(run("int main() { return 1.5 != 1.5; }"), 0);
+        assert_eq!(run("int main() { float x; x = 0.5; return x == 0.5; }"), 1);
+        // NaN はどの値とも等しくない
+        assert_eq!(
+            run("int main() { double z; double n; z = 0; n = z / z; return (n == n) + (n != n) * 2; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { double x; x = 0.0; if (x) return 1; return 2; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { double x; x = 0.5; return !x + (x && 1) * 2; }"),
+            2
+        );
+        assert_eq!(
+            run("int main() { double x; int n; x = 0; n = 0; while (x < 2.0) { x += 0.5; n++; } return n; }"),
+            4
+        );
+    }
+
+    #[test]
+    fn test_float_conversions() {
+        assert_eq!(run("int main() { return (int)3.9; }"), 3);
+        assert_eq!(run("int main() { return (int)-3.9 == -3; }"), 1);
+        assert_eq!(run("int main() { return (char)300.0; }"), 44);
+        assert_eq!(run("int main() { return (float)1 / 2 * 4; }"), 2);
+        assert_eq!(run("int main() { return (double)(float)0.1 != 0.1; }"), 1);
+        assert_eq!(run("int main() { return (float)0.1 == 0.1f; }"), 1);
+        assert_eq!(
+            run("int main() { unsigned long x; x = 18446744073709551615ul; return (double)x == 18446744073709551616.0; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { unsigned long x = 18446744073709551615ul; float f = x; return f > 0; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { unsigned long x = 9223372036854775809ul; return (float)x == 9223372036854775808.0f; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { unsigned long x = 3; return (float)x == 3.0f; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { double x; x = 9223372036854775808.0; return (unsigned long)x == 9223372036854775808ul; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { long x; x = -5; return (double)x == -5.0; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { unsigned char c; c = 200; return (float)c == 200; }"),
+            1
+        );
+        assert_eq!(run("int main() { int x; x = 2.75 * 4; return x; }"), 11);
+        assert_eq!(run("int main() { return 1 ? 2.5 : 1 == 2.5; }"), 2);
+    }
+
+    #[test]
+    fn test_float_parameters() {
+        assert_eq!(
+            run("double half(double x) { return x / 2; } int main() { return half(9) * 2; }"),
+            9
+        );
+        assert_eq!(
+            run("float f(int a, float b, long c, double d) { return a + b + c + d; } int main() { return f(1, 2.5, 3, 4.5); }"),
+            11
+        );
+        assert_eq!(
+            run("double f(double a, double b, double c, double d, double e, double g, double h, double i, double j, int k, double l) { return j * 10 + k + l; } int main() { return f(1, 2, 3, 4, 5, 6, 7, 8, 9, 2, 0.5) * 2; }"),
+            185
+        );
+        assert_eq!(
+            run("int f(int a, int b, int c, int d, int e, int g, int h, double x) { return h + x; } int main() { return f(1, 2, 3, 4, 5, 6, 7, 1.5); }"),
+            8
+        );
+
+        let object = "
+            double add_double(double a, double b) { return a + b; }
+            float mix(int a, float b, long c, double d) { return a * b + c * d; }
+            double many(double a, double b, double c, double d, double e, double f, double g, double h, double i, double j) {
+                return a + b + c + d + e + f + g + h + i * j;
+            }
+        ";
+        assert_eq!(
+            run_with_object(
+                "double add_double(double a, double b); int main() { return add_double(1.25, 2.75); }",
+                Some(object)
+            ),
+            4
+        );
+        assert_eq!(
+            run_with_object(
+                "float mix(int a, float b, long c, double d); int main() { return mix(2, 1.5, 3, 0.5); }",
+                Some(object)
+            ),
+            4
+        );
+        assert_eq!(
+            run_with_object(
+                "double many(double a, double b, double c, double d, double e, double f, double g, double h, double i, double j); int main() { return many(1, 1, 1, 1, 1, 1, 1, 1, 3, 4); }",
+                Some(object)
+            ),
+            20
+        );
+    }
+
+    #[test]
+    fn test_variadic_function() {
+        let sum = "
+            int sum(int n, ...) {
+                va_list ap;
+                int s;
+                int i;
+                va_start(ap, n);
+                s = 0;
+                for (i = 0; i < n; i++)
+                    s += va_arg(ap, int);
+                va_end(ap);
+                return s;
+            }
+        ";
+        assert_eq!(
+            run(&format!("{sum} int main() {{ return sum(3, 1, 2, 3); }}")),
+            6
+        );
+        // 整数レジスタに収まらない引数はスタックから取り出す
+        assert_eq!(
+            run(&format!(
+                "{sum} int main() {{ return sum(9, 1, 2, 3, 4, 5, 6, 7, 8, 9); }}"
+            )),
+            45
+        );
+        assert_eq!(
+            run("double sum(int n, ...) { va_list ap; double s; va_start(ap, n); s = 0; while (n--) s += va_arg(ap, double); return s; }
+                 int main() { return sum(10, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.5) * 2; }"),
+            111
+        );
+        assert_eq!(
+            run("long f(int n, double x, ...) { va_list ap; long a; double b; char *c; va_start(ap, x); a = va_arg(ap, long); b = va_arg(ap, double); c = va_arg(ap, char *); return a + b * x + *c; }
+                 int main() { return f(0, 2.0, 3l, 1.5, \"A\") - 'A'; }"),
+            6
+        );
+        // 仮引数の並びに対応しない float は double に拡張して渡される
+        assert_eq!(
+            run(
+                "double f(int n, ...) { va_list ap; va_start(ap, n); return va_arg(ap, double); }
+                 int main() { float x; x = 2.5; return f(0, x) * 2; }"
+            ),
+            5
+        );
+        // 拡張される型を指定した va_arg は、拡張後の型で取り出して変換する
+        assert_eq!(
+            run("int f(int n, ...) { va_list ap; float x; va_start(ap, n); x = va_arg(ap, float); return x == 1.5; }
+                 int main() { return f(1, 1.5); }"),
+            1
+        );
+        assert_eq!(
+            run("int f(int n, ...) { va_list ap; char c; short s; va_start(ap, n); c = va_arg(ap, char); s = va_arg(ap, short); return c + s; }
+                 int main() { return f(2, 'a', 3); }"),
+            100
+        );
+        assert_eq!(
+            run("int f(int n, ...) { va_list ap; va_start(ap, n); return sizeof(va_arg(ap, char)); } int main() { return f(0); }"),
+            1
+        );
+        assert_eq!(
+            run("int f(int n, ...) { va_list ap; va_list aq; int a; va_start(ap, n); va_arg(ap, int); va_copy(aq, ap); a = va_arg(ap, int); return a * 10 + va_arg(aq, int); }
+                 int main() { return f(0, 1, 2); }"),
+            22
+        );
+        assert_eq!(
+            run("int g(va_list ap) { return va_arg(ap, int); }
+                 int f(int n, ...) { va_list ap; int a; va_start(ap, n); a = g(ap); return a * 10 + g(ap); }
+                 int main() { return f(0, 3, 4); }"),
+            34
+        );
+    }
+
+    #[test]
+    fn test_variadic_libc() {
+        let decls = "
+            int sprintf(char *buf, char *fmt, ...);
+            int vsprintf(char *buf, char *fmt, va_list ap);
+            int strcmp(char *a, char *b);
+        ";
+        assert_eq!(
+            run(&format!(
+                "{decls} int main() {{ char buf[32]; sprintf(buf, \"%d %.2f %s\", 3, 1.5, \"x\"); return strcmp(buf, \"3 1.50 x\"); }}"
+            )),
+            0
+        );
+        assert_eq!(
+            run(&format!(
+                "{decls} int format(char *buf, char *fmt, ...) {{ va_list ap; int n; va_start(ap, fmt); n = vsprintf(buf, fmt, ap); va_end(ap); return n; }}
+                 int main() {{ char buf[64]; format(buf, \"%d %g %ld %g %g %g %g %g %g %g %s\", 1, 0.5, 2l, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.5, \"end\"); return strcmp(buf, \"1 0.5 2 1 2 3 4 5 6 7.5 end\"); }}"
+            )),
+            0
+        );
+        // 引数名を省略したプロトタイプ
+        assert_eq!(
+            run("int printf(char *, ...); int main() { return printf(\"%d-%s\\n\", 42, \"ab\"); }"),
+            6
+        );
+        assert_eq!(
+            run("int sprintf(char *, char *, ...); int strcmp(char *, char *); long f(int (*)[3], long);
+                 int main() { char buf[16]; sprintf(buf, \"%.1f\", 2.5); return strcmp(buf, \"2.5\"); }"),
+            0
+        );
+
+        let object = "
+            #include <stdarg.h>
+            double average(int n, ...) {
+                va_list ap;
+                double sum = 0;
+                va_start(ap, n);
+                for (int i = 0; i < n; i++)
+                    sum += va_arg(ap, double);
+                va_end(ap);
+                return sum / n;
+            }
+        ";
+        assert_eq!(
+            run_with_object(
+                "double average(int n, ...); int main() { float x; x = 4; return average(3, 1.0, 2.0, x); }",
+                Some(object)
+            ),
+            2
+        );
+
+        let object = "
+            int sum(int n, ...);
+            int call_sum(void) { return sum(7, 1, 2, 3, 4, 5, 6, 7); }
+        ";
+        assert_eq!(
+            run_with_object(
+                "int call_sum(void);
+                 int sum(int n, ...) { va_list ap; int s; va_start(ap, n); s = 0; while (n--) s += va_arg(ap, int); return s; }
+                 int main() { return call_sum(); }",
+                Some(object)
+            ),
+            28
+        );
+    }
+
+    #[test]
+    fn test_struct_by_value() {
+        let pair = "struct P { int x; int y; };";
+        assert_eq!(
+            run(&format!(
+                "{pair} int f(struct P p) {{ return p.x * 10 + p.y; }}
+                 int main() {{ struct P p; p.x = 3; p.y = 4; return f(p); }}"
+            )),
+            34
+        );
+        assert_eq!(
+            run(&format!(
+                "{pair} struct P f(int x, int y) {{ struct P p; p.x = x; p.y = y; return p; }}
+                 int main() {{ struct P p; p = f(5, 6); return p.x * 10 + p.y; }}"
+            )),
+            56
+        );
+        // 戻り値の構造体のメンバを直接参照する
+        assert_eq!(
+            run(&format!(
+                "{pair} struct P f(int x) {{ struct P p; p.x = x; p.y = x + 1; return p; }}
+                 int main() {{ return f(7).y; }}"
+            )),
+            8
+        );
+        // 引数の構造体はコピーなので、呼び出し先での変更は呼び出し元に影響しない
+        assert_eq!(
+            run(&format!(
+                "{pair} int f(struct P p) {{ p.x = 9; return p.x; }}
+                 int main() {{ struct P p; p.x = 1; return f(p) * 10 + p.x; }}"
+            )),
+            91
+        );
+        assert_eq!(
+            run(
+                "struct D { double x; double y; }; double f(struct D d) { return d.x - d.y; }
+                 struct D g(double x) { struct D d; d.x = x; d.y = x / 2; return d; }
+                 int main() { return f(g(10)); }"
+            ),
+            5
+        );
+        assert_eq!(
+            run("struct M { int a; double b; }; struct M f(struct M m) { m.a = m.a + m.b; m.b = m.a * 2; return m; }
+                 int main() { struct M m; m.a = 1; m.b = 2.5; m = f(m); return m.a + m.b; }"),
+            9
+        );
+        assert_eq!(
+            run("struct C { char c[3]; }; struct C f(struct C c) { c.c[2] = c.c[0] + c.c[1]; return c; }
+                 int main() { struct C c; c.c[0] = 2; c.c[1] = 3; c.c[2] = 0; return f(c).c[2]; }"),
+            5
+        );
+        assert_eq!(
+            run("struct F { float a; float b; float c; }; struct F f(struct F s) { s.c = s.a * s.b; return s; }
+                 int main() { struct F s; s.a = 1.5; s.b = 4; return f(s).c; }"),
+            6
+        );
+        // 16 バイトを超える構造体はメモリで受け渡す
+        let big = "struct B { long a; long b; long c; };";
+        assert_eq!(
+            run(&format!(
+                "{big} struct B f(struct B b, int n) {{ b.a += n; b.c = b.a + b.b; return b; }}
+                 int main() {{ struct B b; b.a = 1; b.b = 2; b.c = 0; b = f(b, 4); return b.c; }}"
+            )),
+            7
+        );
+        // 整数レジスタが足りなければ、構造体全体をスタックで渡す
+        assert_eq!(
+            run(&format!(
+                "{pair} int f(long a, long b, long c, long d, long e, struct P p, long g) {{ return p.x * 10 + p.y + g; }}
+                 int main() {{ struct P p; p.x = 2; p.y = 3; return f(1, 2, 3, 4, 5, p, 6); }}"
+            )),
+            29
+        );
+        assert_eq!(
+            run(&format!(
+                "{big} long f(int a, int b, int c, int d, int e, int g, struct B x, int h) {{ return x.a + x.b + x.c + h + g; }}
+                 int main() {{ struct B b; b.a = 1; b.b = 2; b.c = 3; return f(0, 0, 0, 0, 0, 10, b, 20); }}"
+            )),
+            36
+        );
+    }
+
+    #[test]
+    fn test_struct_by_value_abi() {
+        let decls = "
+            struct P { int x; int y; };
+            struct D { double x; double y; };
+            struct M { char c; double d; };
+            struct F { float a; float b; float c; };
+            struct B { long a; long b; long c; };
+        ";
+        let object = format!(
+            "{decls}
+            int sum_p(struct P p) {{ return p.x + p.y; }}
+            struct P make_p(int x, int y) {{ struct P p = {{ x, y }}; return p; }}
+            double sum_d(struct D d) {{ return d.x + d.y; }}
+            struct D make_d(double x, double y) {{ struct D d = {{ x, y }}; return d; }}
+            struct M make_m(char c, double d) {{ struct M m = {{ c, d }}; return m; }}
+            float sum_f(struct F f) {{ return f.a + f.b + f.c; }}
+            struct F make_f(float a) {{ struct F f = {{ a, a * 2, a * 3 }}; return f; }}
+            long sum_b(struct B b) {{ return b.a + b.b + b.c; }}
+            struct B make_b(long a) {{ struct B b = {{ a, a + 1, a + 2 }}; return b; }}
+            "
+        );
+        let object = Some(object.as_str());
+
+        let run_c = |body: &str| run_with_object(&format!("{decls} {body}"), object);
+        assert_eq!(
+            run_c("int sum_p(struct P p); int main() { struct P p; p.x = 3; p.y = 4; return sum_p(p); }"),
+            7
+        );
+        assert_eq!(
+            run_c("struct P make_p(int x, int y); int main() { return make_p(5, 6).y; }"),
+            6
+        );
+        assert_eq!(
+            run_c("double sum_d(struct D d); int main() { struct D d; d.x = 1.5; d.y = 2.5; return sum_d(d); }"),
+            4
+        );
+        assert_eq!(
+            run_c("struct D make_d(double x, double y); int main() { struct D d; d = make_d(1.5, 2.5); return d.x * d.y * 4; }"),
+            15
+        );
+        assert_eq!(
+            run_c("struct M make_m(char c, double d); int main() { struct M m; m = make_m(3, 0.5); return m.c + m.d * 4; }"),
+            5
+        );
+        assert_eq!(
+            run_c("float sum_f(struct F f); int main() { struct F f; f.a = 1; f.b = 2; f.c = 3.5; return sum_f(f) * 2; }"),
+            13
+        );
+        assert_eq!(
+            run_c("struct F make_f(float a); int main() { return make_f(1.5).c * 2; }"),
+            9
+        );
+        assert_eq!(
+            run_c("long sum_b(struct B b); int main() { struct B b; b.a = 1; b.b = 2; b.c = 3; return sum_b(b); }"),
+            6
+        );
+        assert_eq!(
+            run_c("struct B make_b(long a); int main() { struct B b; b = make_b(4); return b.a + b.b + b.c; }"),
+            15
+        );
+
+        // C のコードから呼ばれる関数
+        let object = "
+            struct M { char c; double d; };
+            struct B { long a; long b; long c; };
+            struct M twice_m(struct M m);
+            struct B twice_b(struct B b);
+            int check(void) {
+                struct M m = { 3, 1.25 };
+                struct B b = { 1, 2, 3 };
+                m = twice_m(m);
+                b = twice_b(b);
+                return m.c == 6 && m.d == 2.5 && b.a == 2 && b.b == 4 && b.c == 6;
+            }
+        ";
+        assert_eq!(
+            run_with_object(
+                "struct M { char c; double d; };
+                 struct B { long a; long b; long c; };
+                 int check(void);
+                 struct M twice_m(struct M m) { m.c *= 2; m.d *= 2; return m; }
+                 struct B twice_b(struct B b) { b.a *= 2; b.b *= 2; b.c *= 2; return b; }
+                 int main() { return check(); }",
+                Some(object)
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn test_scalar_initializer() {
+        assert_eq!(run("int main() { int x = 3; return x; }"), 3);
+        assert_eq!(run("int main() { int x = 3, y = x + 1; return y; }"), 4);
+        assert_eq!(run("int main() { int x = {5}; return x; }"), 5);
+        assert_eq!(run("int main() { char c = 300; return c; }"), 44);
+        assert_eq!(
+            run("int main() { double d = 3; int i = d / 2 * 4; return i; }"),
+            6
+        );
+        assert_eq!(run("int main() { int x = 2; int *p = &x; return *p; }"), 2);
+        assert_eq!(run("int main() { int x = 1; { int x = 2; } return x; }"), 1);
+    }
+
+    #[test]
+    fn test_array_initializer() {
+        assert_eq!(
+            run("int main() { int a[3] = {1, 2, 3}; return a[0] + a[1] * a[2]; }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { int a[] = {1, 2, 3, 4}; return sizeof(a); }"),
+            16
+        );
+        // 省略した要素は 0 になる
+        assert_eq!(
+            run("int main() { int a[4] = {1, 2,}; return a[2] + a[3]; }"),
+            0
+        );
+        assert_eq!(
+            run("int main() { int x = 9; int a[3]; a[2] = 5; { int b[3] = {1}; return b[1] + b[2]; } }"),
+            0
+        );
+        assert_eq!(
+            run("int main() { int a[2][3] = {{1, 2, 3}, {4, 5, 6}}; return a[0][1] + a[1][2]; }"),
+            8
+        );
+        // 内側の括弧は省略できる
+        assert_eq!(
+            run("int main() { int a[2][3] = {1, 2, 3, 4}; return a[1][0] * 10 + a[1][1]; }"),
+            40
+        );
+        assert_eq!(
+            run("int main() { int a[][2] = {{1, 2}, {3}}; return sizeof(a) + a[1][0]; }"),
+            19
+        );
+    }
+
+    #[test]
+    fn test_string_initializer() {
+        assert_eq!(
+            run("int main() { char s[] = \"abc\"; return sizeof(s); }"),
+            4
+        );
+        assert_eq!(run("int main() { char s[] = \"abc\"; return s[2]; }"), 99);
+        assert_eq!(
+            run("int main() { char s[] = {\"abc\"}; return sizeof(s) + s[1]; }"),
+            102
+        );
+        assert_eq!(
+            run("int main() { char s[6] = \"ab\"; return s[1] + s[2] + s[5]; }"),
+            98
+        );
+        // 配列の長さがちょうど文字列の長さなら、終端の NUL 文字は入らない
+        assert_eq!(
+            run("int main() { char s[3] = \"abc\"; return sizeof(s) + s[2]; }"),
+            102
+        );
+        assert_eq!(
+            run("int main() { char s[2][4] = {\"ab\", \"cde\"}; return s[1][2]; }"),
+            101
+        );
+        assert_eq!(run("int main() { char *p = \"xyz\"; return p[1]; }"), 121);
+    }
+
+    #[test]
+    fn test_struct_initializer() {
+        let point = "struct P { int x; int y; };";
+        assert_eq!(
+            run(&format!(
+                "{point} int main() {{ struct P p = {{1, 2}}; return p.x * 10 + p.y; }}"
+            )),
+            12
+        );
+        assert_eq!(
+            run(&format!(
+                "{point} int main() {{ struct P p = {{3}}; return p.x * 10 + p.y; }}"
+            )),
+            30
+        );
+        assert_eq!(
+            run(&format!(
+                "{point} int main() {{ struct P p = {{1, 2}}; struct P q = p; return q.x * 10 + q.y; }}"
+            )),
+            12
+        );
+        assert_eq!(
+            run(&format!(
+                "{point} int main() {{ struct P a[] = {{{{1, 2}}, {{3, 4}}}}; return sizeof(a) + a[1].y; }}"
+            )),
+            20
+        );
+        assert_eq!(
+            run(&format!(
+                "{point} int main() {{ struct P a[2] = {{1, 2, 3}}; return a[1].x * 10 + a[1].y; }}"
+            )),
+            30
+        );
+        assert_eq!(
+            run("int main() { struct { char c; int a[2]; double d; } s = {1, {2, 3}, 0.5}; return s.c + s.a[0] + s.a[1] + s.d * 2; }"),
+            7
+        );
+        assert_eq!(
+            run("int main() { union { int i; char c[4]; } u = {0x01020304}; return u.c[0]; }"),
+            4
+        );
+        assert_eq!(
+            run("int main() { union { char c[4]; int i; } u = {{1, 1}}; return u.i == 257; }"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_designated_initializer() {
+        assert_eq!(
+            run("int main() { int a[5] = {[2] = 3, 4, [0] = 1}; return a[0] == 1 && a[1] == 0 && a[2] == 3 && a[3] == 4; }"),
+            1
+        );
+        assert_eq!(
+            run("int main() { int a[] = {[4] = 1}; return sizeof(a); }"),
+            20
+        );
+        assert_eq!(
+            run("struct P { int x; int y; }; int main() { struct P p = {.y = 2, .x = 1}; return p.x * 10 + p.y; }"),
+            12
+        );
+        assert_eq!(
+            run("struct P { int x; int y; int z; }; int main() { struct P p = {.y = 2, 3}; return p.x * 100 + p.y * 10 + p.z; }"),
+            23
+        );
+        assert_eq!(
+            run("struct P { int x; int y; }; int main() { struct P a[3] = {[1].y = 5, [2] = {6}}; return a[0].x + a[1].x + a[1].y * 10 + a[2].x; }"),
+            56
+        );
+        assert_eq!(
+            run("int main() { int a[2][2] = {[1][0] = 3, [0] = {1, 2}}; return a[0][0] + a[0][1] + a[1][0] * 10 + a[1][1]; }"),
+            33
+        );
+        assert_eq!(
+            run("int main() { union { int i; char c; } u = {.c = 7}; return u.c; }"),
+            7
+        );
+    }
+
+    #[test]
+    fn test_global_initializer() {
+        assert_eq!(run("int g = 3; int main() { return g; }"), 3);
+        assert_eq!(run("long g = -1; int main() { return g == -1; }"), 1);
+        assert_eq!(run("char g = 1 + 2 * 3; int main() { return g; }"), 7);
+        assert_eq!(
+            run("int g[] = {1, 2, 3}; int main() { return sizeof(g) + g[2]; }"),
+            15
+        );
+        assert_eq!(
+            run("int g[4] = {1, [2] = 5}; int main() { return g[1] + g[2] + g[3]; }"),
+            5
+        );
+        assert_eq!(
+            run("char g[] = \"abc\"; int main() { return sizeof(g) + g[1]; }"),
+            102
+        );
+        assert_eq!(
+            run("double g = 1.5; float h = 1 / 4.0; int main() { return g * 2 + h * 8; }"),
+            5
+        );
+        assert_eq!(run("double g = 3; int main() { return g == 3.0; }"), 1);
+        assert_eq!(run("int g = 2.75 * 2; int main() { return g; }"), 5);
+        assert_eq!(
+            run("struct P { char c; long l; } g[2] = {{1, 2}, {.l = 3}}; int main() { return g[0].c + g[0].l + g[1].c + g[1].l; }"),
+            6
+        );
+        assert_eq!(
+            run("union { int i; char c[4]; } g = {.c = {1, 1}}; int main() { return g.i == 257; }"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_global_initializer_relocation() {
+        assert_eq!(run("int x = 3; int *p = &x; int main() { return *p; }"), 3);
+        assert_eq!(run("char *s = \"hello\"; int main() { return s[1]; }"), 101);
+        assert_eq!(
+            run("int a[3] = {1, 2, 3}; int *p = a + 2; int main() { return *p; }"),
+            3
+        );
+        assert_eq!(
+            run("int a[3] = {1, 2, 3}; int *p = &a[1]; int main() { return *p; }"),
+            2
+        );
+        assert_eq!(
+            run("int a[3] = {1, 2, 3}; int *p = &a[2] - 1; int main() { return *p; }"),
+            2
+        );
+        assert_eq!(
+            run("char *names[] = {\"ab\", \"cd\", 0}; int main() { return names[1][1] + (names[2] == 0); }"),
+            101
+        );
+        assert_eq!(
+            run("struct P { int x; int y; } p = {1, 2}; int *q = &p.y; int main() { return *q; }"),
+            2
+        );
+        assert_eq!(
+            run("struct N { struct N *next; int v; }; struct N a = {&a, 4}; int main() { return a.next->next->v; }"),
+            4
+        );
+        assert_eq!(
+            run("int f() { return 7; } int (*fp)() = f; int main() { return fp != 0; }"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_compound_literal() {
+        let point = "struct P { int x; int y; };";
+        assert_eq!(
+            run(&format!(
+                "{point} int main() {{ struct P p = (struct P){{1, 2}}; return p.x * 10 + p.y; }}"
+            )),
+            12
+        );
+        assert_eq!(
+            run(&format!(
+                "{point} int main() {{ return (struct P){{.y = 5}}.y + (struct P){{3}}.y; }}"
+            )),
+            5
+        );
+        assert_eq!(
+            run("int main() { int *p = (int[]){1, 2, 3}; return p[2]; }"),
+            3
+        );
+        assert_eq!(run("int main() { return (int[]){4, 5, 6}[1]; }"), 5);
+        assert_eq!(run("int main() { return sizeof((int[]){1, 2, 3}); }"), 12);
+        assert_eq!(run("int main() { return sizeof (char[]){\"abcd\"}; }"), 5);
+        assert_eq!(run("int main() { return (int){7} + (long){1}; }"), 8);
+        assert_eq!(
+            run("int main() { return (int)(char){1} + sizeof(int); }"),
+            5
+        );
+        // 複合リテラルは左辺値
+        assert_eq!(
+            run(&format!(
+                "{point} int main() {{ struct P *p = &(struct P){{1, 2}}; p->x = 7; return p->x + p->y; }}"
+            )),
+            9
+        );
+        // ブロックの中の複合リテラルは、評価するたびに初期化される
+        assert_eq!(
+            run("int main() { int s = 0; int i; for (i = 0; i < 3; i++) { int *p = (int[2]){i}; s += p[0] + p[1]; p[1] = 9; } return s; }"),
+            3
+        );
+        assert_eq!(
+            run(&format!(
+                "{point} int f(struct P p) {{ return p.x - p.y; }} int main() {{ return f((struct P){{9, 4}}); }}"
+            )),
+            5
+        );
+    }
+
+    #[test]
+    fn test_file_scope_compound_literal() {
+        assert_eq!(
+            run("int *p = (int[]){1, 2, 3}; int main() { return p[1]; }"),
+            2
+        );
+        assert_eq!(
+            run("struct P { int x; int y; }; struct P *q = &(struct P){3, 4}; int main() { return q->x + q->y; }"),
+            7
+        );
+        assert_eq!(
+            run("char **names = (char *[]){\"ab\", \"cd\"}; int main() { return names[1][0]; }"),
+            99
+        );
+        // 静的記憶域を持つので、書き換えた値が残る
+        assert_eq!(
+            run("int *p = (int[]){0}; int f() { return ++p[0]; } int main() { f(); f(); return f(); }"),
+            3
+        );
+    }
 }
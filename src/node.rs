@@ -1,9 +1,43 @@
-use std::collections::HashSet;
+use std::{cell::Cell, rc::Rc};
+
+use crate::types::{add_type, Member, Type};
 
 #[derive(Debug)]
 pub struct Program {
+    pub globals: Vec<Rc<Obj>>,
+    pub functions: Vec<Function>,
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    pub return_ty: Type,
+    pub params: Vec<Rc<Obj>>,
+    pub locals: Vec<Rc<Obj>>, // params を含む
     pub body: Node,
-    pub locals: HashSet<String>,
+    pub va_area: Option<Rc<Obj>>, // 可変長引数の関数で、引数レジスタを退避する領域
+}
+
+#[derive(Debug)]
+pub struct Obj {
+    pub name: String,
+    pub ty: Type,
+    pub is_local: bool,
+    pub offset: Cell<i32>, // ローカル変数の %rbp からのオフセット
+
+    // グローバル変数
+    pub is_static: bool,
+    pub is_read_only: bool,
+    pub init_data: Option<Vec<u8>>,
+    pub relocations: Vec<Relocation>,
+}
+
+/// 初期値のうち、他の大域変数のアドレスを埋め込む箇所
+#[derive(Debug)]
+pub struct Relocation {
+    pub offset: usize, // init_data の中での位置
+    pub label: String,
+    pub addend: i64,
 }
 
 #[derive(Debug)]
@@ -11,6 +45,7 @@ pub struct Node {
     pub kind: NodeKind,
     pub lhs: Option<Box<Node>>,
     pub rhs: Option<Box<Node>>,
+    pub ty: Option<Type>,
 }
 
 #[derive(Debug)]
@@ -19,29 +54,108 @@ pub enum NodeKind {
     Sub,
     Multiply,
     Div,
+    Mod,
+    Neg, // 単項マイナス
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
 
     Equal,
     NotEqual,
     LessThan,
     LessThanOrEqual,
 
-    Num(i32),
+    Addr,
+    Deref,
+
+    Not,
+    LogicalAnd,
+    LogicalOr,
+    Conditional(Box<Node>, Box<Node>, Box<Node>), // condition, then, else
+    Comma,
+
+    Num(i64),
+    FNum(f64),
+    Cast, // lhs を node.ty の型に変換する
 
     Assign,
-    Var(String),
+    MemZero(Rc<Obj>), // ローカル変数の領域を 0 で埋める
+    Var(Rc<Obj>),
+    Member(Member), // 構造体・共用体のメンバアクセス（lhs が構造体）
+    FunCall(String, Vec<Node>, Option<Rc<Obj>>), // name, arguments, 戻り値の構造体を受け取る領域
+    VaArg,          // lhs の va_list から次の引数を取り出し、そのアドレスを返す
 
     ExpressionStatement,
     Block(Vec<Node>), // body
     Return,
     If(Box<Node>, Box<Node>, Box<Option<Node>>), // condition, then, else
+    While(Box<Node>, Box<Node>),                 // condition, body
+    For(
+        Box<Option<Node>>, // init
+        Box<Option<Node>>, // condition
+        Box<Option<Node>>, // increment
+        Box<Node>,         // body
+    ),
+    Do(Box<Node>, Box<Node>), // body, condition
+    Switch {
+        condition: Box<Node>,
+        body: Box<Node>,
+        cases: Vec<i64>, // case ラベルの値。Case ノードはこの添字を持つ
+        has_default: bool,
+    },
+    Case(usize, Box<Node>), // cases の添字, statement
+    Default(Box<Node>),     // statement
+    Break,
+    Continue,
+    Goto(String),
+    Label(String, Box<Node>), // name, statement
 }
 
 impl Node {
-    pub fn new_num(n: i32) -> Self {
+    pub fn new(kind: NodeKind) -> Self {
         Node {
-            kind: NodeKind::Num(n),
+            kind,
             lhs: None,
             rhs: None,
+            ty: None,
+        }
+    }
+
+    pub fn new_unary(kind: NodeKind, lhs: Node) -> Self {
+        Node {
+            kind,
+            lhs: Some(Box::new(lhs)),
+            rhs: None,
+            ty: None,
+        }
+    }
+
+    pub fn new_binary(kind: NodeKind, lhs: Node, rhs: Node) -> Self {
+        Node {
+            kind,
+            lhs: Some(Box::new(lhs)),
+            rhs: Some(Box::new(rhs)),
+            ty: None,
+        }
+    }
+
+    pub fn new_num(n: i64) -> Self {
+        Node::new(NodeKind::Num(n))
+    }
+
+    /// `expr` を `ty` 型に変換するノードを作る
+    pub fn new_cast(mut expr: Node, ty: Type) -> Self {
+        add_type(&mut expr);
+
+        Node {
+            kind: NodeKind::Cast,
+            lhs: Some(Box::new(expr)),
+            rhs: None,
+            ty: Some(ty),
         }
     }
 }
@@ -1,27 +1,149 @@
-use std::collections::HashSet;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
 
 use crate::{
-    node::{Node, NodeKind},
+    node::{Function, Node, NodeKind, Obj, Program, Relocation},
     token::Token,
+    types::{add_type, get_common_type, get_member, Member, StructType, Type},
 };
 
+/// 通常の識別子の名前空間に入るもの
+enum Symbol {
+    Var(Rc<Obj>),
+    Typedef(Type),
+    EnumConstant(i32),
+}
+
+#[derive(Default)]
+struct Scope {
+    symbols: HashMap<String, Symbol>,
+    tags: HashMap<String, Type>, // 構造体・共用体・列挙型のタグ
+}
+
+/// 解析中の switch 文に含まれる case と default
+struct SwitchCases {
+    ty: Type, // 制御式の型。case の値はこの型に変換される
+    values: Vec<i64>,
+    has_default: bool,
+}
+
+/// 宣言の記憶クラス指定子
+#[derive(Default)]
+struct VarAttr {
+    is_typedef: bool,
+}
+
+/// 変数の初期化子。配列と構造体は要素ごとの初期化子を子に持つ
+struct Initializer {
+    ty: Type,
+    expr: Option<Node>, // スカラーの初期値、または構造体全体を初期化する式
+    children: Vec<Initializer>,
+    is_flexible: bool,     // 長さを初期化子から決める配列
+    member: Option<usize>, // 共用体で初期化するメンバの添字
+}
+
+impl Initializer {
+    fn new(ty: Type, is_flexible: bool) -> Self {
+        let children = match &ty {
+            Type::Array(_, len) if is_flexible && *len < 0 => vec![],
+            Type::Array(base, len) => (0..*len)
+                .map(|_| Initializer::new(*base.clone(), false))
+                .collect(),
+            Type::Struct(st) | Type::Union(st) => st
+                .borrow()
+                .members
+                .iter()
+                .map(|member| Initializer::new(member.ty.clone(), false))
+                .collect(),
+            _ => vec![],
+        };
+
+        Initializer {
+            is_flexible: matches!(ty, Type::Array(_, len) if is_flexible && len < 0),
+            ty,
+            expr: None,
+            children,
+            member: None,
+        }
+    }
+
+    /// 配列の i 番目の要素の初期化子。長さを決めている途中の配列なら要素を増やす
+    fn element(&mut self, i: usize) -> Option<&mut Initializer> {
+        if self.is_flexible {
+            while self.children.len() <= i {
+                let base = self.ty.base().unwrap().clone();
+                self.children.push(Initializer::new(base, false));
+            }
+        }
+        self.children.get_mut(i)
+    }
+}
+
+/// ローカル変数の初期化で、値を代入する要素までの経路
+enum Designator {
+    Index(usize),
+    Member(Member),
+}
+
 pub struct Parser {
     tokens: Vec<Token>,
     cursor: usize,
-    locals: HashSet<String>,
+    locals: Vec<Rc<Obj>>,
+    globals: Vec<Rc<Obj>>,
+    scopes: Vec<Scope>,
+    unique_id: usize,
+
+    // 関数の中の文の解析状態
+    current_switch: Option<SwitchCases>,
+    break_depth: usize,    // break で抜けられる文の入れ子の深さ
+    continue_depth: usize, // continue できるループの入れ子の深さ
+    labels: Vec<String>,
+    gotos: Vec<String>,
+    return_ty: Option<Type>,  // 解析中の関数の戻り値の型
+    va_area: Option<Rc<Obj>>, // 解析中の関数が可変長引数を取る場合の引数の退避領域
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
+        // va_list は System V ABI の定義どおり、次の構造体を要素に持つ長さ 1 の配列とする
+        let va_elem = StructType::new_struct(vec![
+            ("gp_offset".to_string(), Type::UInt),
+            ("fp_offset".to_string(), Type::UInt),
+            (
+                "overflow_arg_area".to_string(),
+                Type::pointer_to(Type::Void),
+            ),
+            ("reg_save_area".to_string(), Type::pointer_to(Type::Void)),
+        ]);
+        let va_list = Type::array_of(Type::Struct(Rc::new(RefCell::new(va_elem))), 1);
+
+        let mut builtins = Scope::default();
+        builtins
+            .symbols
+            .insert("va_list".to_string(), Symbol::Typedef(va_list));
+
         Parser {
             tokens,
             cursor: 0,
-            locals: HashSet::new(),
+            locals: vec![],
+            globals: vec![],
+            scopes: vec![builtins],
+            unique_id: 0,
+            current_switch: None,
+            break_depth: 0,
+            continue_depth: 0,
+            labels: vec![],
+            gotos: vec![],
+            return_ty: None,
+            va_area: None,
         }
     }
 
-    pub fn parse(&mut self) -> (Node, HashSet<String>) {
-        (self.program(), self.locals.clone())
+    pub fn parse(&mut self) -> Program {
+        self.program()
     }
 
     fn peek(&self) -> Option<Token> {
@@ -48,273 +170,2338 @@ impl Parser {
         panic!("expected {token:?}, but got {next:?}");
     }
 
-    fn expect_number(&mut self) -> i32 {
+    fn expect_number(&mut self) -> (i64, Type) {
         let next = self.peek();
 
-        if let Some(next) = &next {
-            if matches!(next, Token::Num(_)) {
-                if let Token::Num(n) = next {
-                    self.cursor += 1;
-                    return *n;
-                }
-            }
+        if let Some(Token::Num(value, ty)) = next {
+            self.cursor += 1;
+            return (value, ty);
         }
 
         panic!("expected number, but got {next:?}");
     }
 
-    fn program(&mut self) -> Node {
-        self.expect(Token::LeftBrace);
-        self.compound_statement()
+    fn expect_ident(&mut self) -> String {
+        let next = self.peek();
+
+        if let Some(Token::Ident(name)) = next {
+            self.cursor += 1;
+            return name;
+        }
+
+        panic!("expected identifier, but got {next:?}");
     }
 
-    fn statement(&mut self) -> Node {
-        if self.consume(Token::Return) {
-            let node = Node {
-                kind: NodeKind::Return,
-                lhs: Some(Box::new(self.expression())),
-                rhs: None,
-            };
-            self.expect(Token::Semicolon);
+    fn peek_nth(&self, n: usize) -> Option<Token> {
+        self.tokens.get(self.cursor + n).cloned()
+    }
 
-            return node;
+    fn is_typename(&self, token: Option<Token>) -> bool {
+        match token {
+            Some(
+                Token::Void
+                | Token::Char
+                | Token::Short
+                | Token::Int
+                | Token::Long
+                | Token::Float
+                | Token::Double
+                | Token::Signed
+                | Token::Unsigned
+                | Token::Struct
+                | Token::Union
+                | Token::Enum
+                | Token::Typedef,
+            ) => true,
+            Some(Token::Ident(name)) => self.find_typedef(&name).is_some(),
+            _ => false,
         }
+    }
 
-        if self.consume(Token::LeftBrace) {
-            return self.compound_statement();
+    fn enter_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    fn leave_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn current_scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut().unwrap()
+    }
+
+    fn find_tag(&self, name: &str) -> Option<Type> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.tags.get(name))
+            .cloned()
+    }
+
+    fn new_lvar(&mut self, name: String, ty: Type) -> Rc<Obj> {
+        let var = Rc::new(Obj {
+            name,
+            ty,
+            is_local: true,
+            offset: Cell::new(0),
+            is_static: false,
+            is_read_only: false,
+            init_data: None,
+            relocations: vec![],
+        });
+        self.locals.push(var.clone());
+        self.current_scope()
+            .symbols
+            .insert(var.name.clone(), Symbol::Var(var.clone()));
+
+        var
+    }
+
+    fn new_gvar(
+        &mut self,
+        name: String,
+        ty: Type,
+        init_data: Option<Vec<u8>>,
+        relocations: Vec<Relocation>,
+    ) -> Rc<Obj> {
+        let var = Rc::new(Obj {
+            name,
+            ty,
+            is_local: false,
+            offset: Cell::new(0),
+            is_static: false,
+            is_read_only: false,
+            init_data,
+            relocations,
+        });
+        self.globals.push(var.clone());
+        self.current_scope()
+            .symbols
+            .insert(var.name.clone(), Symbol::Var(var.clone()));
+
+        var
+    }
+
+    /// 名前を大域スコープに登録する。データとしては出力しないので globals には含めない。
+    /// 関数と、初期化子を解析している間の大域変数に使う
+    fn declare_global(&mut self, name: String, ty: Type) {
+        let function = Rc::new(Obj {
+            name: name.clone(),
+            ty,
+            is_local: false,
+            offset: Cell::new(0),
+            is_static: false,
+            is_read_only: false,
+            init_data: None,
+            relocations: vec![],
+        });
+        self.scopes[0].symbols.insert(name, Symbol::Var(function));
+    }
+
+    fn new_unique_name(&mut self) -> String {
+        let name = format!(".L..{}", self.unique_id);
+        self.unique_id += 1;
+        name
+    }
+
+    fn new_string_literal(&mut self, mut bytes: Vec<u8>) -> Rc<Obj> {
+        bytes.push(0);
+
+        let var = Rc::new(Obj {
+            name: self.new_unique_name(),
+            ty: Type::array_of(Type::Char, bytes.len() as i32),
+            is_local: false,
+            offset: Cell::new(0),
+            is_static: true,
+            is_read_only: true,
+            init_data: Some(bytes),
+            relocations: vec![],
+        });
+        self.globals.push(var.clone());
+
+        var
+    }
+
+    /// 名前のない大域変数を作る。ファイルスコープの複合リテラルに使う
+    fn new_anon_gvar(
+        &mut self,
+        ty: Type,
+        init_data: Vec<u8>,
+        relocations: Vec<Relocation>,
+    ) -> Rc<Obj> {
+        let var = Rc::new(Obj {
+            name: self.new_unique_name(),
+            ty,
+            is_local: false,
+            offset: Cell::new(0),
+            is_static: true,
+            is_read_only: false,
+            init_data: Some(init_data),
+            relocations,
+        });
+        self.globals.push(var.clone());
+
+        var
+    }
+
+    fn find_symbol(&self, name: &str) -> Option<&Symbol> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.symbols.get(name))
+    }
+
+    fn find_var(&self, name: &str) -> Option<Rc<Obj>> {
+        match self.find_symbol(name) {
+            Some(Symbol::Var(var)) => Some(var.clone()),
+            _ => None,
         }
+    }
 
-        if self.consume(Token::If) {
-            self.expect(Token::LeftParen);
-            let condition = self.expression();
-            self.expect(Token::RightParen);
-            let then = self.statement();
+    fn find_typedef(&self, name: &str) -> Option<Type> {
+        match self.find_symbol(name) {
+            Some(Symbol::Typedef(ty)) => Some(ty.clone()),
+            _ => None,
+        }
+    }
 
-            let els = if self.consume(Token::Else) {
-                Some(self.statement())
-            } else {
-                None
-            };
+    // program = (typedef | function-definition | global-variable)*
+    fn program(&mut self) -> Program {
+        let mut functions = vec![];
 
-            return Node {
-                kind: NodeKind::If(Box::new(condition), Box::new(then), Box::new(els)),
-                lhs: None,
-                rhs: None,
-            };
+        while !self.consume(Token::Eof) {
+            let mut attr = VarAttr::default();
+            let base_ty = self.declspec(Some(&mut attr));
+
+            if attr.is_typedef {
+                self.parse_typedef(base_ty);
+                continue;
+            }
+
+            if self.is_function() {
+                if let Some(function) = self.function(base_ty) {
+                    functions.push(function);
+                }
+                continue;
+            }
+
+            self.global_variable(base_ty);
         }
 
-        self.expression_statement()
+        Program {
+            globals: self.globals.clone(),
+            functions,
+        }
     }
 
-    fn expression_statement(&mut self) -> Node {
-        if self.consume(Token::Semicolon) {
-            return Node {
-                kind: NodeKind::Block(vec![]),
-                lhs: None,
-                rhs: None,
-            };
+    // 関数の定義か宣言であるかを先読みして判定する
+    fn is_function(&mut self) -> bool {
+        if self.peek() == Some(Token::Semicolon) {
+            return false;
         }
 
-        let node = Node {
-            kind: NodeKind::ExpressionStatement,
-            lhs: Some(Box::new(self.expression())),
-            rhs: None,
-        };
-        self.expect(Token::Semicolon);
+        let start = self.cursor;
+        let (ty, _) = self.declarator(Type::Int);
+        self.cursor = start;
 
-        node
+        matches!(ty, Type::Func { .. })
     }
 
-    fn compound_statement(&mut self) -> Node {
-        let mut statements = vec![];
+    // function-definition = declspec declarator ("{" compound-statement | ";")
+    fn function(&mut self, base_ty: Type) -> Option<Function> {
+        let (ty, name) = self.declarator(base_ty);
+        self.declare_global(name.clone(), ty.clone());
 
-        while !self.consume(Token::RightBrace) {
-            statements.push(self.statement())
+        let Type::Func {
+            params,
+            return_ty,
+            is_variadic,
+        } = ty
+        else {
+            panic!("関数の定義ではありません: {name}");
+        };
+
+        // 本体のない関数の宣言
+        if self.consume(Token::Semicolon) {
+            return None;
         }
 
-        Node {
-            kind: NodeKind::Block(statements),
-            lhs: None,
-            rhs: None,
+        self.locals = vec![];
+        self.enter_scope();
+        let params = params
+            .into_iter()
+            .map(|(name, ty)| self.new_lvar(name, ty))
+            .collect();
+
+        // va_list のヘッダ（24バイト）と、6個の整数レジスタ・8個の XMM レジスタの退避領域
+        self.va_area = is_variadic
+            .then(|| self.new_lvar("__va_area__".to_string(), Type::array_of(Type::Long, 25)));
+
+        self.labels = vec![];
+        self.gotos = vec![];
+        self.return_ty = Some(*return_ty.clone());
+
+        self.expect(Token::LeftBrace);
+        let body = self.compound_statement();
+        self.leave_scope();
+
+        // goto は後方にあるラベルにも飛べるので、関数の最後にまとめて解決する
+        for label in &self.gotos {
+            if !self.labels.contains(label) {
+                panic!("未定義のラベルです: {label}");
+            }
         }
-    }
 
-    fn expression(&mut self) -> Node {
-        self.assign()
+        Some(Function {
+            name,
+            return_ty: *return_ty,
+            params,
+            locals: self.locals.clone(),
+            body,
+            va_area: self.va_area.take(),
+        })
     }
 
-    fn assign(&mut self) -> Node {
-        let mut node = self.equality();
+    // global-variable = declspec (declarator ("=" initializer)? ("," declarator ("=" initializer)?)*)? ";"
+    fn global_variable(&mut self, base_ty: Type) {
+        let mut i = 0;
+        while !self.consume(Token::Semicolon) {
+            if i > 0 {
+                self.expect(Token::Comma);
+            }
+            i += 1;
 
-        if self.consume(Token::Assign) {
-            node = Node {
-                kind: NodeKind::Assign,
-                lhs: Some(Box::new(node)),
-                rhs: Some(Box::new(self.assign())),
+            let (ty, name) = self.declarator(base_ty.clone());
+            if !self.consume(Token::Assign) {
+                check_array_len(&ty, &name);
+                self.new_gvar(name, ty, None, vec![]);
+                continue;
             }
-        }
 
-        node
+            // 初期化子の中で変数自身のアドレスを使えるよう、先に名前を登録しておく
+            self.declare_global(name.clone(), ty.clone());
+            let init = self.initializer(ty);
+            let ty = init.ty.clone();
+            let (data, relocations) = gvar_initializer(init);
+            self.new_gvar(name, ty, Some(data), relocations);
+        }
     }
 
-    fn equality(&mut self) -> Node {
-        let mut node = self.relational();
+    // declspec = ("typedef" | "void" | "char" | "short" | "int" | "long"
+    //             | "float" | "double" | "signed" | "unsigned"
+    //             | "struct" struct-union-decl | "union" struct-union-decl
+    //             | "enum" enum-specifier | typedef-name)+
+    //
+    // 型指定子の並びの順序は自由なので（`long int` と `int long` は同じ型）、
+    // それぞれの型指定子が現れた回数を `counter` に数えてから型を決める。
+    // 記憶クラス指定子は `attr` を渡された場合にのみ受け付ける
+    fn declspec(&mut self, mut attr: Option<&mut VarAttr>) -> Type {
+        // long は2回まで現れるので、各指定子に2ビットずつ割り当てる
+        const VOID: u32 = 1 << 0;
+        const CHAR: u32 = 1 << 2;
+        const SHORT: u32 = 1 << 4;
+        const INT: u32 = 1 << 6;
+        const LONG: u32 = 1 << 8;
+        const FLOAT: u32 = 1 << 10;
+        const DOUBLE: u32 = 1 << 12;
+        const OTHER: u32 = 1 << 14;
+        const SIGNED: u32 = 1 << 16;
+        const UNSIGNED: u32 = 1 << 18;
 
-        loop {
-            if self.consume(Token::Equal) {
-                node = Node {
-                    kind: NodeKind::Equal,
-                    lhs: Some(Box::new(node)),
-                    rhs: Some(Box::new(self.relational())),
+        let mut ty = None;
+        let mut counter = 0;
+
+        while self.is_typename(self.peek()) {
+            if self.consume(Token::Typedef) {
+                match attr.as_deref_mut() {
+                    Some(attr) => attr.is_typedef = true,
+                    None => panic!("記憶クラス指定子はここでは使えません"),
                 }
-            } else if self.consume(Token::NotEqual) {
-                node = Node {
-                    kind: NodeKind::NotEqual,
-                    lhs: Some(Box::new(node)),
-                    rhs: Some(Box::new(self.relational())),
+                continue;
+            }
+
+            let token = self.peek().unwrap();
+
+            // 構造体や typedef 名は他の型指定子と組み合わせられない。
+            // `typedef int T; { int T; }` の二つ目の T は変数名になる
+            if let Token::Struct | Token::Union | Token::Enum | Token::Ident(_) = token {
+                if counter > 0 {
+                    break;
                 }
-            } else {
-                return node;
+                self.cursor += 1;
+
+                ty = Some(match token {
+                    Token::Struct => self.struct_union_decl(false),
+                    Token::Union => self.struct_union_decl(true),
+                    Token::Enum => self.enum_specifier(),
+                    Token::Ident(name) => self.find_typedef(&name).unwrap(),
+                    _ => unreachable!(),
+                });
+                counter += OTHER;
+                continue;
             }
+            self.cursor += 1;
+
+            counter += match &token {
+                Token::Void => VOID,
+                Token::Char => CHAR,
+                Token::Short => SHORT,
+                Token::Int => INT,
+                Token::Long => LONG,
+                Token::Float => FLOAT,
+                Token::Double => DOUBLE,
+                Token::Signed => SIGNED,
+                Token::Unsigned => UNSIGNED,
+                _ => unreachable!(),
+            };
+
+            let is = |combinations: &[u32]| combinations.contains(&counter);
+            ty = Some(if is(&[VOID]) {
+                Type::Void
+            } else if is(&[CHAR, SIGNED + CHAR]) {
+                Type::Char
+            } else if is(&[UNSIGNED + CHAR]) {
+                Type::UChar
+            } else if is(&[SHORT, SHORT + INT, SIGNED + SHORT, SIGNED + SHORT + INT]) {
+                Type::Short
+            } else if is(&[UNSIGNED + SHORT, UNSIGNED + SHORT + INT]) {
+                Type::UShort
+            } else if is(&[INT, SIGNED, SIGNED + INT]) {
+                Type::Int
+            } else if is(&[UNSIGNED, UNSIGNED + INT]) {
+                Type::UInt
+            } else if is(&[
+                LONG,
+                LONG + INT,
+                LONG + LONG,
+                LONG + LONG + INT,
+                SIGNED + LONG,
+                SIGNED + LONG + INT,
+                SIGNED + LONG + LONG,
+                SIGNED + LONG + LONG + INT,
+            ]) {
+                Type::Long
+            } else if is(&[
+                UNSIGNED + LONG,
+                UNSIGNED + LONG + INT,
+                UNSIGNED + LONG + LONG,
+                UNSIGNED + LONG + LONG + INT,
+            ]) {
+                Type::ULong
+            } else if is(&[FLOAT]) {
+                Type::Float
+            } else if is(&[DOUBLE, LONG + DOUBLE]) {
+                // long double は double として扱う
+                Type::Double
+            } else {
+                panic!("無効な型です: {token:?}");
+            });
+        }
+
+        match ty {
+            Some(ty) => ty,
+            None => panic!("expected type name, but got {:?}", self.peek()),
         }
     }
 
-    fn relational(&mut self) -> Node {
-        let mut node = self.add();
+    // enum-specifier = ident? "{" enum-list? "}"
+    //                | ident ("{" enum-list? "}")?
+    //
+    // enum-list      = ident ("=" const-expr)? ("," ident ("=" const-expr)?)* ","?
+    fn enum_specifier(&mut self) -> Type {
+        let tag = match self.peek() {
+            Some(Token::Ident(tag)) => {
+                self.cursor += 1;
+                Some(tag)
+            }
+            _ => None,
+        };
 
-        loop {
-            if self.consume(Token::LeftAngleBracket) {
-                node = Node {
-                    kind: NodeKind::LessThan,
-                    lhs: Some(Box::new(node)),
-                    rhs: Some(Box::new(self.add())),
-                }
-            } else if self.consume(Token::LessThanOrEqual) {
-                node = Node {
-                    kind: NodeKind::LessThanOrEqual,
-                    lhs: Some(Box::new(node)),
-                    rhs: Some(Box::new(self.add())),
-                }
-            } else if self.consume(Token::RightAngleBracket) {
-                node = Node {
-                    kind: NodeKind::LessThan,
-                    lhs: Some(Box::new(self.add())),
-                    rhs: Some(Box::new(node)),
-                }
-            } else if self.consume(Token::GreaterThanOrEqual) {
-                node = Node {
-                    kind: NodeKind::LessThanOrEqual,
-                    lhs: Some(Box::new(self.add())),
-                    rhs: Some(Box::new(node)),
+        if let Some(tag) = &tag {
+            if !self.consume(Token::LeftBrace) {
+                return match self.find_tag(tag) {
+                    Some(Type::Enum) => Type::Enum,
+                    Some(_) => panic!("列挙型ではありません: {tag}"),
+                    None => panic!("未定義の列挙型です: {tag}"),
+                };
+            }
+        } else {
+            self.expect(Token::LeftBrace);
+        }
+
+        let mut value = 0;
+        let mut i = 0;
+        while !self.consume(Token::RightBrace) {
+            if i > 0 {
+                self.expect(Token::Comma);
+                // 末尾のカンマ
+                if self.consume(Token::RightBrace) {
+                    break;
                 }
-            } else {
-                return node;
             }
+            i += 1;
+
+            let name = self.expect_ident();
+            if self.consume(Token::Assign) {
+                value = self.const_expr() as i32;
+            }
+
+            self.current_scope()
+                .symbols
+                .insert(name, Symbol::EnumConstant(value));
+            value += 1;
+        }
+
+        if let Some(tag) = tag {
+            self.current_scope().tags.insert(tag, Type::Enum);
         }
+
+        Type::Enum
     }
 
-    fn add(&mut self) -> Node {
-        let mut node = self.mul();
+    // struct-union-decl = ident? ("{" struct-members)?
+    fn struct_union_decl(&mut self, is_union: bool) -> Type {
+        let new_type = |st| {
+            if is_union {
+                Type::Union(st)
+            } else {
+                Type::Struct(st)
+            }
+        };
 
-        loop {
-            if self.consume(Token::Plus) {
-                node = Node {
-                    kind: NodeKind::Add,
-                    lhs: Some(Box::new(node)),
-                    rhs: Some(Box::new(self.mul())),
+        let tag = match self.peek() {
+            Some(Token::Ident(tag)) => {
+                self.cursor += 1;
+                Some(tag)
+            }
+            _ => None,
+        };
+
+        if let Some(tag) = &tag {
+            if !self.consume(Token::LeftBrace) {
+                if let Some(ty) = self.find_tag(tag) {
+                    check_tag_kind(&ty, is_union, tag);
+                    return ty;
                 }
-            } else if self.consume(Token::Minus) {
-                node = Node {
-                    kind: NodeKind::Sub,
-                    lhs: Some(Box::new(node)),
-                    rhs: Some(Box::new(self.mul())),
+
+                // 前方宣言。メンバは後で同じスコープで定義されたときに埋める
+                let ty = new_type(StructType::new_incomplete());
+                self.current_scope().tags.insert(tag.clone(), ty.clone());
+                return ty;
+            }
+        } else {
+            self.expect(Token::LeftBrace);
+        }
+
+        let members = self.struct_members();
+        let layout = if is_union {
+            StructType::new_union(members)
+        } else {
+            StructType::new_struct(members)
+        };
+
+        // 同じスコープで前方宣言されていれば、その型を完成させる
+        if let Some(tag) = &tag {
+            if let Some(ty @ (Type::Struct(st) | Type::Union(st))) =
+                self.current_scope().tags.get(tag)
+            {
+                if !st.borrow().is_complete {
+                    check_tag_kind(ty, is_union, tag);
+                    *st.borrow_mut() = layout;
+                    return new_type(st.clone());
                 }
-            } else {
-                return node;
             }
         }
+
+        let ty = new_type(Rc::new(RefCell::new(layout)));
+        if let Some(tag) = tag {
+            self.current_scope().tags.insert(tag, ty.clone());
+        }
+
+        ty
     }
 
-    fn mul(&mut self) -> Node {
-        let mut node = self.unary();
+    // struct-members = (declspec declarator ("," declarator)* ";")* "}"
+    fn struct_members(&mut self) -> Vec<(String, Type)> {
+        let mut members = vec![];
 
-        loop {
-            if self.consume(Token::Star) {
-                node = Node {
-                    kind: NodeKind::Multiply,
-                    lhs: Some(Box::new(node)),
-                    rhs: Some(Box::new(self.unary())),
-                }
-            } else if self.consume(Token::Slash) {
-                node = Node {
-                    kind: NodeKind::Div,
-                    lhs: Some(Box::new(node)),
-                    rhs: Some(Box::new(self.unary())),
+        while !self.consume(Token::RightBrace) {
+            let base_ty = self.declspec(None);
+
+            let mut i = 0;
+            while !self.consume(Token::Semicolon) {
+                if i > 0 {
+                    self.expect(Token::Comma);
                 }
-            } else {
-                return node;
+                i += 1;
+
+                let (ty, name) = self.declarator(base_ty.clone());
+                members.push((name, ty));
             }
         }
+
+        members
     }
 
-    fn unary(&mut self) -> Node {
-        if self.consume(Token::Plus) {
-            return self.unary();
+    // declarator = "*"* ("(" declarator ")" | ident) type-suffix
+    fn declarator(&mut self, mut ty: Type) -> (Type, String) {
+        while self.consume(Token::Star) {
+            ty = Type::pointer_to(ty);
         }
 
-        if self.consume(Token::Minus) {
-            return Node {
-                kind: NodeKind::Sub,
-                lhs: Some(Box::new(Node::new_num(0))),
-                rhs: Some(Box::new(self.unary())),
+        if self.consume(Token::LeftParen) {
+            // `int (*x)[3]` のような入れ子の宣言子では、括弧の外側の型が先に決まる必要があるため、
+            // 括弧の中を一度読み飛ばして外側の型を求めてから、括弧の中に戻って解析する
+            let start = self.cursor;
+            self.declarator(Type::Int);
+            self.expect(Token::RightParen);
+            let ty = self.type_suffix(ty);
+            let end = self.cursor;
+
+            self.cursor = start;
+            let (ty, name) = self.declarator(ty);
+            self.expect(Token::RightParen);
+            self.cursor = end;
+
+            return (ty, name);
+        }
+
+        let name = self.expect_ident();
+        let ty = self.type_suffix(ty);
+
+        (ty, name)
+    }
+
+    // type-suffix = "(" func-params
+    //             | "[" const-expr? "]" type-suffix
+    //             | ε
+    fn type_suffix(&mut self, ty: Type) -> Type {
+        if self.consume(Token::LeftParen) {
+            return self.function_params(ty);
+        }
+
+        if self.consume(Token::LeftBracket) {
+            // 長さを省略した配列は、長さを -1 としておき初期化子から決める
+            let len = if self.peek() == Some(Token::RightBracket) {
+                -1
+            } else {
+                self.const_expr() as i32
             };
+            self.expect(Token::RightBracket);
+            let ty = self.type_suffix(ty);
+            return Type::array_of(ty, len);
         }
 
-        self.primary()
+        ty
     }
 
-    fn primary(&mut self) -> Node {
+    // func-params = (param ("," param)* ("," "...")?)? ")"
+    // param       = declspec declarator
+    fn function_params(&mut self, return_ty: Type) -> Type {
+        let mut params: Vec<(String, Type)> = vec![];
+        let mut is_variadic = false;
+
+        // `f(void)` は引数を取らない関数
+        if self.peek() == Some(Token::Void) && self.peek_nth(1) == Some(Token::RightParen) {
+            self.cursor += 2;
+            return Type::Func {
+                return_ty: Box::new(return_ty),
+                params,
+                is_variadic,
+            };
+        }
+
+        while !self.consume(Token::RightParen) {
+            if !params.is_empty() {
+                self.expect(Token::Comma);
+            }
+
+            if !params.is_empty() && self.consume(Token::Ellipsis) {
+                is_variadic = true;
+                self.expect(Token::RightParen);
+                break;
+            }
+
+            // `int printf(char *, ...);` のように名前を省略した引数は、抽象宣言子として読む
+            let base_ty = self.declspec(None);
+            let (mut ty, name) = if self.has_declarator_name() {
+                self.declarator(base_ty)
+            } else {
+                (self.abstract_declarator(base_ty), String::new())
+            };
+            if !name.is_empty() && params.iter().any(|(param, _)| *param == name) {
+                panic!("引数名が重複しています: {name}");
+            }
+
+            // 配列型の引数はポインタとして受け取る
+            if let Type::Array(base, _) = ty {
+                ty = Type::Ptr(base);
+            }
+
+            params.push((name, ty));
+        }
+
+        Type::Func {
+            return_ty: Box::new(return_ty),
+            params,
+            is_variadic,
+        }
+    }
+
+    /// 続く宣言子が識別子を含むかどうかを、先頭の `*` と `(` を読み飛ばして調べる
+    fn has_declarator_name(&self) -> bool {
+        let mut n = 0;
+        while let Some(Token::Star | Token::LeftParen) = self.peek_nth(n) {
+            n += 1;
+        }
+        let token = self.peek_nth(n);
+        matches!(token, Some(Token::Ident(_))) && !self.is_typename(token)
+    }
+
+    // abstract-declarator = "*"* ("(" abstract-declarator ")")? type-suffix
+    fn abstract_declarator(&mut self, mut ty: Type) -> Type {
+        while self.consume(Token::Star) {
+            ty = Type::pointer_to(ty);
+        }
+
         if self.consume(Token::LeftParen) {
-            let node = self.expression();
+            let start = self.cursor;
+            self.abstract_declarator(Type::Int);
             self.expect(Token::RightParen);
-            return node;
+            let ty = self.type_suffix(ty);
+            let end = self.cursor;
+
+            self.cursor = start;
+            let ty = self.abstract_declarator(ty);
+            self.expect(Token::RightParen);
+            self.cursor = end;
+
+            return ty;
         }
 
-        if let Some(Token::Ident(name)) = &self.peek() {
-            self.cursor += 1;
+        self.type_suffix(ty)
+    }
+
+    // type-name = declspec abstract-declarator
+    fn typename(&mut self) -> Type {
+        let base_ty = self.declspec(None);
+        self.abstract_declarator(base_ty)
+    }
 
-            if !self.locals.contains(name) {
-                self.locals.insert(name.clone());
+    // declaration = declspec (declarator ("=" initializer)? ("," declarator ("=" initializer)?)*)? ";"
+    fn declaration(&mut self, base_ty: Type) -> Node {
+        let mut nodes = vec![];
+
+        let mut i = 0;
+        while !self.consume(Token::Semicolon) {
+            if i > 0 {
+                self.expect(Token::Comma);
+            }
+            i += 1;
+
+            let (ty, name) = self.declarator(base_ty.clone());
+            if !self.consume(Token::Assign) {
+                check_array_len(&ty, &name);
+                self.new_lvar(name, ty);
+                continue;
             }
 
-            return Node {
-                kind: NodeKind::Var(name.clone()),
-                lhs: None,
-                rhs: None,
+            // 長さを省略した配列は初期化子から型が決まるので、変数はその後で作る
+            let (var, init) = match ty {
+                Type::Array(_, len) if len < 0 => {
+                    let init = self.initializer(ty);
+                    (self.new_lvar(name, init.ty.clone()), init)
+                }
+                _ => {
+                    let var = self.new_lvar(name, ty.clone());
+                    (var, self.initializer(ty))
+                }
             };
+            nodes.push(Node::new_unary(
+                NodeKind::ExpressionStatement,
+                lvar_initializer(var, init),
+            ));
         }
 
-        Node::new_num(self.expect_number())
+        Node::new(NodeKind::Block(nodes))
     }
-}
 
-#[cfg(test)]
-mod test {
-    use crate::{parser, token::Token};
+    // initializer = initializer2
+    //
+    // 長さを省略した配列は、初期化子の要素数から長さを決める
+    fn initializer(&mut self, ty: Type) -> Initializer {
+        let mut init = Initializer::new(ty, true);
+        self.initializer2(&mut init);
+
+        if init.is_flexible {
+            let base = init.ty.base().unwrap().clone();
+            init.ty = Type::array_of(base, init.children.len() as i32);
+        }
+
+        init
+    }
+
+    // initializer2 = string-initializer | array-initializer | struct-initializer
+    //              | union-initializer | "{" assign "}" | assign
+    fn initializer2(&mut self, init: &mut Initializer) {
+        match init.ty.clone() {
+            Type::Array(base, _) => {
+                if let (Some(Token::Str(bytes)), true) =
+                    (self.peek(), base.is_integer() && base.size() == 1)
+                {
+                    self.cursor += 1;
+                    string_initializer(init, bytes);
+                } else if let (Some(Token::LeftBrace), Some(Token::Str(bytes)), true) = (
+                    self.peek(),
+                    self.peek_nth(1),
+                    base.is_integer() && base.size() == 1,
+                ) {
+                    // char s[] = {"abc"} のように波括弧で囲まれた文字列
+                    self.cursor += 2;
+                    string_initializer(init, bytes);
+                    self.consume(Token::Comma);
+                    self.expect(Token::RightBrace);
+                } else if self.consume(Token::LeftBrace) {
+                    self.array_initializer1(init);
+                } else {
+                    self.array_initializer2(init);
+                }
+            }
+            Type::Struct(_) | Type::Union(_) => {
+                if self.consume(Token::LeftBrace) {
+                    if let Type::Struct(_) = init.ty {
+                        self.struct_initializer1(init);
+                    } else {
+                        self.union_initializer1(init);
+                    }
+                    return;
+                }
+
+                // 同じ型の構造体の式であれば、それで全体を初期化する
+                let start = self.cursor;
+                let mut expr = self.assign();
+                add_type(&mut expr);
+                if expr.ty == Some(init.ty.clone()) {
+                    init.expr = Some(expr);
+                    return;
+                }
+                self.cursor = start;
+
+                if let Type::Struct(_) = init.ty {
+                    self.struct_initializer2(init);
+                } else if !init.children.is_empty() {
+                    init.member = Some(0);
+                    self.initializer2(&mut init.children[0]);
+                }
+            }
+            _ => {
+                // スカラーも括弧で囲んで初期化できる
+                if self.consume(Token::LeftBrace) {
+                    self.initializer2(init);
+                    self.consume(Token::Comma);
+                    self.expect(Token::RightBrace);
+                    return;
+                }
+
+                let mut expr = self.assign();
+                add_type(&mut expr);
+                init.expr = Some(expr);
+            }
+        }
+    }
+
+    // array-initializer1 = "{" (array-element ("," array-element)* ","?)? "}"
+    // array-element      = "[" const-expr "]" designation | initializer2
+    fn array_initializer1(&mut self, init: &mut Initializer) {
+        let mut i = 0;
+        let mut first = true;
+        while !self.consume_end() {
+            if !first {
+                self.expect(Token::Comma);
+            }
+            first = false;
+
+            if self.consume(Token::LeftBracket) {
+                i = self.array_designator(init);
+                self.designation(init.element(i).unwrap());
+            } else {
+                match init.element(i) {
+                    Some(element) => self.initializer2(element),
+                    None => panic!("初期化子の要素が多すぎます: {:?}", init.ty),
+                }
+            }
+            i += 1;
+        }
+    }
+
+    // array-initializer2 = initializer2 ("," initializer2)*
+    //
+    // 括弧を省略した配列の初期化子。配列の長さの分だけ要素を読む
+    fn array_initializer2(&mut self, init: &mut Initializer) {
+        let mut i = 0;
+        while i < init.children.len() && !self.is_end() {
+            let start = self.cursor;
+            if i > 0 {
+                self.expect(Token::Comma);
+            }
+
+            // 指示子は、括弧で囲まれた外側の初期化子のもの
+            if let Some(Token::LeftBracket | Token::Dot) = self.peek() {
+                self.cursor = start;
+                return;
+            }
+
+            self.initializer2(&mut init.children[i]);
+            i += 1;
+        }
+    }
+
+    // struct-initializer1 = "{" (struct-element ("," struct-element)* ","?)? "}"
+    // struct-element      = "." ident designation | initializer2
+    fn struct_initializer1(&mut self, init: &mut Initializer) {
+        let mut i = 0;
+        let mut first = true;
+        while !self.consume_end() {
+            if !first {
+                self.expect(Token::Comma);
+            }
+            first = false;
+
+            if self.consume(Token::Dot) {
+                i = self.struct_designator(init);
+                self.designation(&mut init.children[i]);
+            } else {
+                match init.children.get_mut(i) {
+                    Some(member) => self.initializer2(member),
+                    None => panic!("初期化子の要素が多すぎます: {:?}", init.ty),
+                }
+            }
+            i += 1;
+        }
+    }
+
+    // struct-initializer2 = initializer2 ("," initializer2)*
+    //
+    // 括弧を省略した構造体の初期化子。メンバの数だけ要素を読む
+    fn struct_initializer2(&mut self, init: &mut Initializer) {
+        let mut i = 0;
+        while i < init.children.len() && !self.is_end() {
+            let start = self.cursor;
+            if i > 0 {
+                self.expect(Token::Comma);
+            }
+
+            if let Some(Token::LeftBracket | Token::Dot) = self.peek() {
+                self.cursor = start;
+                return;
+            }
+
+            self.initializer2(&mut init.children[i]);
+            i += 1;
+        }
+    }
+
+    // union-initializer1 = "{" ("." ident designation | initializer2) ","? "}"
+    //
+    // 共用体は一つのメンバだけを初期化する
+    fn union_initializer1(&mut self, init: &mut Initializer) {
+        if self.consume(Token::Dot) {
+            let i = self.struct_designator(init);
+            init.member = Some(i);
+            self.designation(&mut init.children[i]);
+        } else if !init.children.is_empty() {
+            init.member = Some(0);
+            self.initializer2(&mut init.children[0]);
+        }
+
+        self.consume(Token::Comma);
+        self.expect(Token::RightBrace);
+    }
+
+    // designation = ("[" const-expr "]" | "." ident)* "=" initializer2
+    fn designation(&mut self, init: &mut Initializer) {
+        if self.consume(Token::LeftBracket) {
+            if !matches!(init.ty, Type::Array(..)) {
+                panic!("配列ではない値に添字の指示子があります: {:?}", init.ty);
+            }
+            let i = self.array_designator(init);
+            self.designation(init.element(i).unwrap());
+            return;
+        }
+
+        if self.consume(Token::Dot) {
+            let i = self.struct_designator(init);
+            if let Type::Union(_) = init.ty {
+                init.member = Some(i);
+            }
+            self.designation(&mut init.children[i]);
+            return;
+        }
+
+        self.expect(Token::Assign);
+        self.initializer2(init);
+    }
+
+    // array-designator = const-expr "]"（"[" は読み込み済み）
+    fn array_designator(&mut self, init: &Initializer) -> usize {
+        let i = self.const_expr();
+        self.expect(Token::RightBracket);
+
+        if i < 0 || (!init.is_flexible && i as usize >= init.children.len()) {
+            panic!("配列の範囲外を指定しています: {i}");
+        }
+        i as usize
+    }
+
+    // struct-designator = ident（"." は読み込み済み）
+    fn struct_designator(&mut self, init: &Initializer) -> usize {
+        let name = self.expect_ident();
+        let (Type::Struct(st) | Type::Union(st)) = &init.ty else {
+            panic!("構造体でも共用体でもありません: {:?}", init.ty);
+        };
+
+        match st.borrow().members.iter().position(|m| m.name == name) {
+            Some(i) => i,
+            None => panic!("そのようなメンバはありません: {name}"),
+        }
+    }
+
+    /// 初期化子の並びの終わり（`}` か `,}`）であれば読み進める
+    fn consume_end(&mut self) -> bool {
+        if self.consume(Token::RightBrace) {
+            return true;
+        }
+
+        if self.is_end() {
+            self.cursor += 2;
+            return true;
+        }
+
+        false
+    }
+
+    fn is_end(&self) -> bool {
+        self.peek() == Some(Token::RightBrace)
+            || (self.peek() == Some(Token::Comma) && self.peek_nth(1) == Some(Token::RightBrace))
+    }
+
+    // typedef = declspec (declarator ("," declarator)*)? ";"
+    fn parse_typedef(&mut self, base_ty: Type) {
+        let mut i = 0;
+        while !self.consume(Token::Semicolon) {
+            if i > 0 {
+                self.expect(Token::Comma);
+            }
+            i += 1;
+
+            let (ty, name) = self.declarator(base_ty.clone());
+            self.current_scope()
+                .symbols
+                .insert(name, Symbol::Typedef(ty));
+        }
+    }
+
+    fn statement(&mut self) -> Node {
+        if self.consume(Token::Return) {
+            if self.consume(Token::Semicolon) {
+                return Node::new(NodeKind::Return);
+            }
+
+            let expr = self.expression();
+            self.expect(Token::Semicolon);
+
+            // 戻り値は関数の戻り値の型に変換してから返す
+            let return_ty = self.return_ty.clone().unwrap();
+            if let Type::Struct(_) | Type::Union(_) = return_ty {
+                return Node::new_unary(NodeKind::Return, expr);
+            }
+            return Node::new_unary(NodeKind::Return, Node::new_cast(expr, return_ty));
+        }
+
+        if self.consume(Token::LeftBrace) {
+            return self.compound_statement();
+        }
+
+        if self.consume(Token::If) {
+            self.expect(Token::LeftParen);
+            let condition = self.expression();
+            self.expect(Token::RightParen);
+            let then = self.statement();
+
+            let els = if self.consume(Token::Else) {
+                Some(self.statement())
+            } else {
+                None
+            };
+
+            return Node::new(NodeKind::If(
+                Box::new(condition),
+                Box::new(then),
+                Box::new(els),
+            ));
+        }
+
+        if self.consume(Token::While) {
+            self.expect(Token::LeftParen);
+            let condition = self.expression();
+            self.expect(Token::RightParen);
+            let body = self.loop_body();
+
+            return Node::new(NodeKind::While(Box::new(condition), Box::new(body)));
+        }
+
+        if self.consume(Token::For) {
+            self.expect(Token::LeftParen);
+            let init = if self.consume(Token::Semicolon) {
+                None
+            } else {
+                Some(self.expression_statement())
+            };
+
+            let condition = if self.consume(Token::Semicolon) {
+                None
+            } else {
+                let condition = self.expression();
+                self.expect(Token::Semicolon);
+                Some(condition)
+            };
+
+            let increment = if self.consume(Token::RightParen) {
+                None
+            } else {
+                let increment = self.expression();
+                self.expect(Token::RightParen);
+                Some(increment)
+            };
+
+            let body = self.loop_body();
+
+            return Node::new(NodeKind::For(
+                Box::new(init),
+                Box::new(condition),
+                Box::new(increment),
+                Box::new(body),
+            ));
+        }
+
+        if self.consume(Token::Do) {
+            let body = self.loop_body();
+            self.expect(Token::While);
+            self.expect(Token::LeftParen);
+            let condition = self.expression();
+            self.expect(Token::RightParen);
+            self.expect(Token::Semicolon);
+
+            return Node::new(NodeKind::Do(Box::new(body), Box::new(condition)));
+        }
+
+        if self.consume(Token::Switch) {
+            self.expect(Token::LeftParen);
+            let mut condition = self.expression();
+            self.expect(Token::RightParen);
+            add_type(&mut condition);
+
+            // 制御式には整数拡張を行う
+            let ty = get_common_type(&Type::Int, condition.ty.as_ref().unwrap());
+            let condition = Node::new_cast(condition, ty.clone());
+
+            let outer_switch = self.current_switch.replace(SwitchCases {
+                ty,
+                values: Vec::new(),
+                has_default: false,
+            });
+            self.break_depth += 1;
+            let body = self.statement();
+            self.break_depth -= 1;
+            let switch = std::mem::replace(&mut self.current_switch, outer_switch).unwrap();
+
+            return Node::new(NodeKind::Switch {
+                condition: Box::new(condition),
+                body: Box::new(body),
+                cases: switch.values,
+                has_default: switch.has_default,
+            });
+        }
+
+        if self.consume(Token::Case) {
+            let value = self.const_expr();
+            self.expect(Token::Colon);
+
+            let Some(switch) = self.current_switch.as_mut() else {
+                panic!("switch 文の外で case は使えません");
+            };
+            let value = eval(&Node::new_cast(Node::new_num(value), switch.ty.clone()));
+            if switch.values.contains(&value) {
+                panic!("case の値が重複しています: {value}");
+            }
+            switch.values.push(value);
+            let index = switch.values.len() - 1;
+
+            let statement = self.statement();
+            return Node::new(NodeKind::Case(index, Box::new(statement)));
+        }
+
+        if self.consume(Token::Default) {
+            self.expect(Token::Colon);
+
+            let Some(switch) = self.current_switch.as_mut() else {
+                panic!("switch 文の外で default は使えません");
+            };
+            if switch.has_default {
+                panic!("default が重複しています");
+            }
+            switch.has_default = true;
+
+            let statement = self.statement();
+            return Node::new(NodeKind::Default(Box::new(statement)));
+        }
+
+        if self.consume(Token::Break) {
+            if self.break_depth == 0 {
+                panic!("ループか switch 文の外で break は使えません");
+            }
+            self.expect(Token::Semicolon);
+            return Node::new(NodeKind::Break);
+        }
+
+        if self.consume(Token::Continue) {
+            if self.continue_depth == 0 {
+                panic!("ループの外で continue は使えません");
+            }
+            self.expect(Token::Semicolon);
+            return Node::new(NodeKind::Continue);
+        }
+
+        if self.consume(Token::Goto) {
+            let name = self.expect_ident();
+            self.expect(Token::Semicolon);
+            self.gotos.push(name.clone());
+            return Node::new(NodeKind::Goto(name));
+        }
+
+        if let (Some(Token::Ident(name)), Some(Token::Colon)) = (self.peek(), self.peek_nth(1)) {
+            self.cursor += 2;
+            if self.labels.contains(&name) {
+                panic!("ラベルが重複しています: {name}");
+            }
+            self.labels.push(name.clone());
+
+            let statement = self.statement();
+            return Node::new(NodeKind::Label(name, Box::new(statement)));
+        }
+
+        self.expression_statement()
+    }
+
+    /// ループの本体を、break と continue を使える状態で解析する
+    fn loop_body(&mut self) -> Node {
+        self.break_depth += 1;
+        self.continue_depth += 1;
+        let body = self.statement();
+        self.continue_depth -= 1;
+        self.break_depth -= 1;
+
+        body
+    }
+
+    fn expression_statement(&mut self) -> Node {
+        if self.consume(Token::Semicolon) {
+            return Node::new(NodeKind::Block(vec![]));
+        }
+
+        let node = Node::new_unary(NodeKind::ExpressionStatement, self.expression());
+        self.expect(Token::Semicolon);
+
+        node
+    }
+
+    fn compound_statement(&mut self) -> Node {
+        let mut statements = vec![];
+        self.enter_scope();
+
+        while !self.consume(Token::RightBrace) {
+            // ラベル `T:` の T は typedef 名であっても型ではない
+            let is_label = self.peek_nth(1) == Some(Token::Colon);
+
+            let mut statement = if self.is_typename(self.peek()) && !is_label {
+                let mut attr = VarAttr::default();
+                let base_ty = self.declspec(Some(&mut attr));
+
+                if attr.is_typedef {
+                    self.parse_typedef(base_ty);
+                    continue;
+                }
+
+                self.declaration(base_ty)
+            } else {
+                self.statement()
+            };
+            add_type(&mut statement);
+            statements.push(statement);
+        }
+
+        self.leave_scope();
+        Node::new(NodeKind::Block(statements))
+    }
+
+    // expression = assign ("," expression)?
+    fn expression(&mut self) -> Node {
+        let node = self.assign();
+
+        if self.consume(Token::Comma) {
+            return Node::new_binary(NodeKind::Comma, node, self.expression());
+        }
+
+        node
+    }
+
+    fn const_expr(&mut self) -> i64 {
+        let mut node = self.conditional();
+        add_type(&mut node);
+        eval(&node)
+    }
+
+    // assign = conditional (assign-op assign)?
+    // assign-op = "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>="
+    fn assign(&mut self) -> Node {
+        let node = self.conditional();
+
+        if self.consume(Token::Assign) {
+            return Node::new_binary(NodeKind::Assign, node, self.assign());
+        }
+
+        if self.consume(Token::PlusAssign) {
+            let rhs = self.assign();
+            return self.compound_assign(new_add(node, rhs));
+        }
+
+        if self.consume(Token::MinusAssign) {
+            let rhs = self.assign();
+            return self.compound_assign(new_sub(node, rhs));
+        }
+
+        let kind = match self.peek() {
+            Some(Token::StarAssign) => NodeKind::Multiply,
+            Some(Token::SlashAssign) => NodeKind::Div,
+            Some(Token::PercentAssign) => NodeKind::Mod,
+            Some(Token::AmpersandAssign) => NodeKind::BitAnd,
+            Some(Token::PipeAssign) => NodeKind::BitOr,
+            Some(Token::CaretAssign) => NodeKind::BitXor,
+            Some(Token::ShiftLeftAssign) => NodeKind::ShiftLeft,
+            Some(Token::ShiftRightAssign) => NodeKind::ShiftRight,
+            _ => return node,
+        };
+        self.cursor += 1;
+
+        let rhs = self.assign();
+        self.compound_assign(Node::new_binary(kind, node, rhs))
+    }
+
+    // `A op= B` を `tmp = &A, *tmp = *tmp op B` に変換する。
+    // A のアドレスを一度だけ評価するため、一時変数にポインタを保存しておく
+    fn compound_assign(&mut self, mut binary: Node) -> Node {
+        let mut lhs = *binary.lhs.take().unwrap();
+        let rhs = *binary.rhs.take().unwrap();
+        add_type(&mut lhs);
+
+        let var = self.new_lvar(String::new(), Type::pointer_to(lhs.ty.clone().unwrap()));
+        let tmp = || Node::new(NodeKind::Var(var.clone()));
+
+        let expr1 = Node::new_binary(
+            NodeKind::Assign,
+            tmp(),
+            Node::new_unary(NodeKind::Addr, lhs),
+        );
+        let expr2 = Node::new_binary(
+            NodeKind::Assign,
+            Node::new_unary(NodeKind::Deref, tmp()),
+            Node::new_binary(binary.kind, Node::new_unary(NodeKind::Deref, tmp()), rhs),
+        );
+
+        Node::new_binary(NodeKind::Comma, expr1, expr2)
+    }
+
+    // conditional = logical-or ("?" expression ":" conditional)?
+    fn conditional(&mut self) -> Node {
+        let condition = self.logical_or();
+
+        if !self.consume(Token::Question) {
+            return condition;
+        }
+
+        let then = self.expression();
+        self.expect(Token::Colon);
+        let els = self.conditional();
+
+        Node::new(NodeKind::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(els),
+        ))
+    }
+
+    // logical-or = logical-and ("||" logical-and)*
+    fn logical_or(&mut self) -> Node {
+        let mut node = self.logical_and();
+
+        while self.consume(Token::LogicalOr) {
+            node = Node::new_binary(NodeKind::LogicalOr, node, self.logical_and());
+        }
+
+        node
+    }
+
+    // logical-and = bit-or ("&&" bit-or)*
+    fn logical_and(&mut self) -> Node {
+        let mut node = self.bit_or();
+
+        while self.consume(Token::LogicalAnd) {
+            node = Node::new_binary(NodeKind::LogicalAnd, node, self.bit_or());
+        }
+
+        node
+    }
+
+    // bit-or = bit-xor ("|" bit-xor)*
+    fn bit_or(&mut self) -> Node {
+        let mut node = self.bit_xor();
+
+        while self.consume(Token::Pipe) {
+            node = Node::new_binary(NodeKind::BitOr, node, self.bit_xor());
+        }
+
+        node
+    }
+
+    // bit-xor = bit-and ("^" bit-and)*
+    fn bit_xor(&mut self) -> Node {
+        let mut node = self.bit_and();
+
+        while self.consume(Token::Caret) {
+            node = Node::new_binary(NodeKind::BitXor, node, self.bit_and());
+        }
+
+        node
+    }
+
+    // bit-and = equality ("&" equality)*
+    fn bit_and(&mut self) -> Node {
+        let mut node = self.equality();
+
+        while self.consume(Token::Ampersand) {
+            node = Node::new_binary(NodeKind::BitAnd, node, self.equality());
+        }
+
+        node
+    }
+
+    fn equality(&mut self) -> Node {
+        let mut node = self.relational();
+
+        loop {
+            if self.consume(Token::Equal) {
+                node = Node::new_binary(NodeKind::Equal, node, self.relational());
+            } else if self.consume(Token::NotEqual) {
+                node = Node::new_binary(NodeKind::NotEqual, node, self.relational());
+            } else {
+                return node;
+            }
+        }
+    }
+
+    fn relational(&mut self) -> Node {
+        let mut node = self.shift();
+
+        loop {
+            if self.consume(Token::LeftAngleBracket) {
+                node = Node::new_binary(NodeKind::LessThan, node, self.shift());
+            } else if self.consume(Token::LessThanOrEqual) {
+                node = Node::new_binary(NodeKind::LessThanOrEqual, node, self.shift());
+            } else if self.consume(Token::RightAngleBracket) {
+                node = Node::new_binary(NodeKind::LessThan, self.shift(), node);
+            } else if self.consume(Token::GreaterThanOrEqual) {
+                node = Node::new_binary(NodeKind::LessThanOrEqual, self.shift(), node);
+            } else {
+                return node;
+            }
+        }
+    }
+
+    // shift = add ("<<" add | ">>" add)*
+    fn shift(&mut self) -> Node {
+        let mut node = self.add();
+
+        loop {
+            if self.consume(Token::ShiftLeft) {
+                node = Node::new_binary(NodeKind::ShiftLeft, node, self.add());
+            } else if self.consume(Token::ShiftRight) {
+                node = Node::new_binary(NodeKind::ShiftRight, node, self.add());
+            } else {
+                return node;
+            }
+        }
+    }
+
+    fn add(&mut self) -> Node {
+        let mut node = self.mul();
+
+        loop {
+            if self.consume(Token::Plus) {
+                node = new_add(node, self.mul());
+            } else if self.consume(Token::Minus) {
+                node = new_sub(node, self.mul());
+            } else {
+                return node;
+            }
+        }
+    }
+
+    fn mul(&mut self) -> Node {
+        let mut node = self.cast();
+
+        loop {
+            if self.consume(Token::Star) {
+                node = Node::new_binary(NodeKind::Multiply, node, self.cast());
+            } else if self.consume(Token::Slash) {
+                node = Node::new_binary(NodeKind::Div, node, self.cast());
+            } else if self.consume(Token::Percent) {
+                node = Node::new_binary(NodeKind::Mod, node, self.cast());
+            } else {
+                return node;
+            }
+        }
+    }
+
+    // cast = "(" type-name ")" cast | unary
+    fn cast(&mut self) -> Node {
+        if self.peek() == Some(Token::LeftParen) && self.is_typename(self.peek_nth(1)) {
+            let start = self.cursor;
+            self.expect(Token::LeftParen);
+            let ty = self.typename();
+            self.expect(Token::RightParen);
+
+            // `(type){...}` はキャストではなく複合リテラル
+            if self.peek() == Some(Token::LeftBrace) {
+                self.cursor = start;
+                return self.unary();
+            }
+
+            return Node::new_cast(self.cast(), ty);
+        }
+
+        self.unary()
+    }
+
+    fn unary(&mut self) -> Node {
+        if self.consume(Token::Plus) {
+            return self.cast();
+        }
+
+        if self.consume(Token::Minus) {
+            return Node::new_unary(NodeKind::Neg, self.cast());
+        }
+
+        if self.consume(Token::Ampersand) {
+            return Node::new_unary(NodeKind::Addr, self.cast());
+        }
+
+        if self.consume(Token::Not) {
+            return Node::new_unary(NodeKind::Not, self.cast());
+        }
+
+        if self.consume(Token::Tilde) {
+            return Node::new_unary(NodeKind::BitNot, self.cast());
+        }
+
+        // ++i は i += 1 と同じ
+        if self.consume(Token::Increment) {
+            let node = self.unary();
+            return self.compound_assign(new_add(node, Node::new_num(1)));
+        }
+
+        // --i は i -= 1 と同じ
+        if self.consume(Token::Decrement) {
+            let node = self.unary();
+            return self.compound_assign(new_sub(node, Node::new_num(1)));
+        }
+
+        if self.consume(Token::Star) {
+            return Node::new_unary(NodeKind::Deref, self.cast());
+        }
+
+        if self.consume(Token::Sizeof) {
+            if self.peek() == Some(Token::LeftParen) && self.is_typename(self.peek_nth(1)) {
+                let start = self.cursor;
+                self.expect(Token::LeftParen);
+                let ty = self.typename();
+                self.expect(Token::RightParen);
+
+                // `sizeof (type){...}` は複合リテラルの大きさ
+                if self.peek() != Some(Token::LeftBrace) {
+                    let mut node = Node::new_num(ty.size().into());
+                    node.ty = Some(Type::ULong);
+                    return node;
+                }
+                self.cursor = start;
+            }
+
+            // sizeof の結果の型は size_t (unsigned long)
+            let mut expr = self.unary();
+            add_type(&mut expr);
+            let mut node = Node::new_num(expr.ty.unwrap().size().into());
+            node.ty = Some(Type::ULong);
+            return node;
+        }
+
+        self.postfix()
+    }
+
+    // postfix = (compound-literal | primary) ("[" expression "]" | "." ident | "->" ident | "++" | "--")*
+    fn postfix(&mut self) -> Node {
+        let mut node =
+            if self.peek() == Some(Token::LeftParen) && self.is_typename(self.peek_nth(1)) {
+                self.compound_literal()
+            } else {
+                self.primary()
+            };
+
+        loop {
+            if self.consume(Token::LeftBracket) {
+                // x[y] は *(x+y) の糖衣構文
+                let index = self.expression();
+                self.expect(Token::RightBracket);
+                node = Node::new_unary(NodeKind::Deref, new_add(node, index));
+                continue;
+            }
+
+            if self.consume(Token::Dot) {
+                node = self.struct_ref(node);
+                continue;
+            }
+
+            if self.consume(Token::Arrow) {
+                // x->y は (*x).y の糖衣構文
+                node = self.struct_ref(Node::new_unary(NodeKind::Deref, node));
+                continue;
+            }
+
+            // i++ は (typeof i)((i += 1) - 1) と同じ
+            if self.consume(Token::Increment) {
+                add_type(&mut node);
+                let ty = node.ty.clone().unwrap();
+                let inc = self.compound_assign(new_add(node, Node::new_num(1)));
+                node = Node::new_cast(new_add(inc, Node::new_num(-1)), ty);
+                continue;
+            }
+
+            // i-- は (typeof i)((i -= 1) + 1) と同じ
+            if self.consume(Token::Decrement) {
+                add_type(&mut node);
+                let ty = node.ty.clone().unwrap();
+                let dec = self.compound_assign(new_sub(node, Node::new_num(1)));
+                node = Node::new_cast(new_add(dec, Node::new_num(1)), ty);
+                continue;
+            }
+
+            return node;
+        }
+    }
+
+    // compound-literal = "(" type-name ")" "{" initializer-list "}"
+    fn compound_literal(&mut self) -> Node {
+        self.expect(Token::LeftParen);
+        let ty = self.typename();
+        self.expect(Token::RightParen);
+        if self.peek() != Some(Token::LeftBrace) {
+            panic!("expected {:?}, but got {:?}", Token::LeftBrace, self.peek());
+        }
+
+        let init = self.initializer(ty);
+
+        // ファイルスコープでは静的記憶域を持つ名前のない大域変数になる
+        if self.scopes.len() == 1 {
+            let ty = init.ty.clone();
+            let (data, relocations) = gvar_initializer(init);
+            let var = self.new_anon_gvar(ty, data, relocations);
+            return Node::new(NodeKind::Var(var));
+        }
+
+        // ブロックの中では、評価するたびに初期化される名前のないローカル変数になる
+        let var = self.new_lvar(String::new(), init.ty.clone());
+        Node::new_binary(
+            NodeKind::Comma,
+            lvar_initializer(var.clone(), init),
+            Node::new(NodeKind::Var(var)),
+        )
+    }
+
+    fn struct_ref(&mut self, mut node: Node) -> Node {
+        add_type(&mut node);
+        let name = self.expect_ident();
+        let member = get_member(node.ty.as_ref().unwrap(), &name);
+
+        Node::new_unary(NodeKind::Member(member), node)
+    }
+
+    fn primary(&mut self) -> Node {
+        if self.consume(Token::LeftParen) {
+            let node = self.expression();
+            self.expect(Token::RightParen);
+            return node;
+        }
+
+        if let Some(Token::Str(bytes)) = self.peek() {
+            self.cursor += 1;
+            let var = self.new_string_literal(bytes);
+            return Node::new(NodeKind::Var(var));
+        }
+
+        if let Some(Token::Ident(name)) = self.peek() {
+            self.cursor += 1;
+
+            if self.consume(Token::LeftParen) {
+                return match name.as_str() {
+                    "va_start" | "va_arg" | "va_end" | "va_copy" => self.va_builtin(&name),
+                    _ => self.function_call(name),
+                };
+            }
+
+            return match self.find_symbol(&name) {
+                Some(Symbol::Var(var)) => Node::new(NodeKind::Var(var.clone())),
+                Some(Symbol::EnumConstant(value)) => Node::new_num((*value).into()),
+                _ => panic!("未定義の変数です: {name}"),
+            };
+        }
+
+        if let Some(Token::FNum(value, ty)) = self.peek() {
+            self.cursor += 1;
+            let mut node = Node::new(NodeKind::FNum(value));
+            node.ty = Some(ty);
+            return node;
+        }
+
+        let (value, ty) = self.expect_number();
+        let mut node = Node::new_num(value);
+        node.ty = Some(ty);
+        node
+    }
+
+    fn function_call(&mut self, name: String) -> Node {
+        let (return_ty, params) = match self.find_var(&name).map(|function| function.ty.clone()) {
+            Some(Type::Func {
+                return_ty, params, ..
+            }) => (*return_ty, params),
+            // 宣言されていない関数は int を返すものとみなす
+            _ => (Type::Int, vec![]),
+        };
+
+        let mut args = vec![];
+
+        while !self.consume(Token::RightParen) {
+            if !args.is_empty() {
+                self.expect(Token::Comma);
+            }
+            let mut arg = self.assign();
+            add_type(&mut arg);
+
+            // 引数は仮引数の型に変換して渡す。対応する仮引数がなければ
+            // 既定の実引数拡張を行い、float は double、int より小さい整数は int にする
+            match params.get(args.len()) {
+                Some((_, Type::Struct(_) | Type::Union(_))) => {}
+                Some((_, param_ty)) => arg = Node::new_cast(arg, param_ty.clone()),
+                None => match arg.ty.as_ref().unwrap() {
+                    Type::Float => arg = Node::new_cast(arg, Type::Double),
+                    ty if ty.is_integer() && ty.size() < 4 => arg = Node::new_cast(arg, Type::Int),
+                    _ => {}
+                },
+            }
+            args.push(arg);
+        }
+
+        // 構造体の戻り値は、呼び出し元に確保した一時的な領域に受け取る
+        let ret_buffer = match return_ty {
+            Type::Struct(_) | Type::Union(_) => {
+                Some(self.new_lvar(String::new(), return_ty.clone()))
+            }
+            _ => None,
+        };
+
+        let mut node = Node::new(NodeKind::FunCall(name, args, ret_buffer));
+        node.ty = Some(return_ty);
+        node
+    }
+
+    // va-builtin = "va_start" "(" assign "," assign ")"
+    //            | "va_arg" "(" assign "," type-name ")"
+    //            | "va_end" "(" assign ")"
+    //            | "va_copy" "(" assign "," assign ")"
+    //
+    // va_list は配列なので、ap はその要素の構造体へのポインタとして扱う
+    fn va_builtin(&mut self, name: &str) -> Node {
+        let mut ap = self.assign();
+        add_type(&mut ap);
+        let Some(va_elem) = ap.ty.as_ref().unwrap().base().cloned() else {
+            panic!("va_list ではありません: {ap:?}");
+        };
+
+        let node = match name {
+            "va_start" => {
+                let Some(va_area) = self.va_area.clone() else {
+                    panic!("可変長引数の関数の外で va_start が使われています");
+                };
+                // 最後の仮引数は、退避領域の位置を決めるのには使わない
+                self.expect(Token::Comma);
+                self.assign();
+
+                // 関数の先頭で組み立てたヘッダを ap にコピーする
+                let header = Node::new_cast(
+                    Node::new_unary(NodeKind::Addr, Node::new(NodeKind::Var(va_area))),
+                    Type::pointer_to(va_elem),
+                );
+                Node::new_binary(
+                    NodeKind::Assign,
+                    Node::new_unary(NodeKind::Deref, ap),
+                    Node::new_unary(NodeKind::Deref, header),
+                )
+            }
+            "va_arg" => {
+                self.expect(Token::Comma);
+                let ty = self.typename();
+                if !ty.is_numeric() && !matches!(ty, Type::Ptr(_)) {
+                    panic!("va_arg で取り出せない型です: {ty:?}");
+                }
+
+                // 可変長引数は既定の実引数拡張を受けて渡されるので、
+                // 拡張後の型で取り出してから指定された型に変換する
+                let promoted = match ty {
+                    Type::Float => Type::Double,
+                    ref ty if ty.is_integer() && ty.size() < 4 => Type::Int,
+                    ref ty => ty.clone(),
+                };
+                let mut node = Node::new_unary(NodeKind::VaArg, ap);
+                node.ty = Some(Type::pointer_to(promoted.clone()));
+                let node = Node::new_unary(NodeKind::Deref, node);
+                if promoted == ty {
+                    node
+                } else {
+                    Node::new_cast(node, ty)
+                }
+            }
+            "va_end" => Node::new_cast(ap, Type::Void),
+            "va_copy" => {
+                self.expect(Token::Comma);
+                let src = self.assign();
+                Node::new_binary(
+                    NodeKind::Assign,
+                    Node::new_unary(NodeKind::Deref, ap),
+                    Node::new_unary(NodeKind::Deref, src),
+                )
+            }
+            _ => unreachable!(),
+        };
+
+        self.expect(Token::RightParen);
+        node
+    }
+}
+
+/// struct と union のどちらで参照したタグかが、宣言されたときと一致しているか調べる
+fn check_tag_kind(ty: &Type, is_union: bool, tag: &str) {
+    match (ty, is_union) {
+        (Type::Struct(_), false) | (Type::Union(_), true) => {}
+        (_, true) => panic!("共用体ではありません: {tag}"),
+        (_, false) => panic!("構造体ではありません: {tag}"),
+    }
+}
+
+/// 長さが決まっていない配列の変数を宣言していないか調べる
+fn check_array_len(ty: &Type, name: &str) {
+    if let Type::Array(_, len) = ty {
+        if *len < 0 {
+            panic!("配列の長さが決まっていません: {name}");
+        }
+    }
+}
+
+/// 文字列リテラルで char の配列を初期化する
+fn string_initializer(init: &mut Initializer, mut bytes: Vec<u8>) {
+    bytes.push(0);
+    if init.is_flexible {
+        init.element(bytes.len() - 1);
+    }
+
+    // 配列がちょうど文字列の長さであれば、終端の NUL 文字は入らない
+    for (element, byte) in init.children.iter_mut().zip(bytes) {
+        let mut expr = Node::new_num(byte as i64);
+        add_type(&mut expr);
+        element.expr = Some(expr);
+    }
+}
+
+/// ローカル変数の初期化子を、変数全体を 0 で埋めてから各要素に代入する式にする
+fn lvar_initializer(var: Rc<Obj>, init: Initializer) -> Node {
+    let mut assigns = vec![];
+    create_lvar_init(init, &var, &mut vec![], &mut assigns);
+
+    assigns
+        .into_iter()
+        .fold(Node::new(NodeKind::MemZero(var)), |lhs, assign| {
+            Node::new_binary(NodeKind::Comma, lhs, assign)
+        })
+}
+
+fn create_lvar_init(
+    init: Initializer,
+    var: &Rc<Obj>,
+    designators: &mut Vec<Designator>,
+    nodes: &mut Vec<Node>,
+) {
+    match &init.ty {
+        Type::Array(..) => {
+            for (i, element) in init.children.into_iter().enumerate() {
+                designators.push(Designator::Index(i));
+                create_lvar_init(element, var, designators, nodes);
+                designators.pop();
+            }
+        }
+        Type::Struct(st) | Type::Union(st) if init.expr.is_none() => {
+            let members = st.borrow().members.clone();
+            for (i, (child, member)) in init.children.into_iter().zip(members).enumerate() {
+                if matches!(init.ty, Type::Union(_)) && init.member != Some(i) {
+                    continue;
+                }
+                designators.push(Designator::Member(member));
+                create_lvar_init(child, var, designators, nodes);
+                designators.pop();
+            }
+        }
+        _ => {
+            let Some(expr) = init.expr else {
+                return;
+            };
+
+            let mut lhs = Node::new(NodeKind::Var(var.clone()));
+            for designator in designators.iter() {
+                lhs = match designator {
+                    Designator::Index(i) => {
+                        Node::new_unary(NodeKind::Deref, new_add(lhs, Node::new_num(*i as i64)))
+                    }
+                    Designator::Member(member) => {
+                        Node::new_unary(NodeKind::Member(member.clone()), lhs)
+                    }
+                };
+            }
+
+            nodes.push(Node::new_binary(NodeKind::Assign, lhs, expr));
+        }
+    }
+}
+
+/// 大域変数の初期化子を、初期値のバイト列と他の大域変数のアドレスを埋める箇所にする
+fn gvar_initializer(init: Initializer) -> (Vec<u8>, Vec<Relocation>) {
+    let mut data = vec![0; init.ty.size() as usize];
+    let mut relocations = vec![];
+    write_gvar_data(init, &mut data, 0, &mut relocations);
+    (data, relocations)
+}
+
+/// 大域変数の初期化子を、`data` の `offset` バイト目からのバイト列に書き込む。
+/// 他の大域変数のアドレスは、リンク時に埋めるよう `relocations` に記録する
+fn write_gvar_data(
+    init: Initializer,
+    data: &mut [u8],
+    offset: usize,
+    relocations: &mut Vec<Relocation>,
+) {
+    match &init.ty {
+        Type::Array(base, _) => {
+            let size = base.size() as usize;
+            for (i, element) in init.children.into_iter().enumerate() {
+                write_gvar_data(element, data, offset + size * i, relocations);
+            }
+        }
+        Type::Struct(st) | Type::Union(st) if init.expr.is_none() => {
+            let members = st.borrow().members.clone();
+            for (i, (child, member)) in init.children.into_iter().zip(members).enumerate() {
+                if matches!(init.ty, Type::Union(_)) && init.member != Some(i) {
+                    continue;
+                }
+                write_gvar_data(child, data, offset + member.offset as usize, relocations);
+            }
+        }
+        _ => {
+            let Some(expr) = init.expr else {
+                return;
+            };
+            let size = init.ty.size() as usize;
+            let expr = Node::new_cast(expr, init.ty.clone());
+
+            let bytes = match init.ty {
+                Type::Float => (eval_double(&expr) as f32).to_le_bytes().to_vec(),
+                Type::Double => eval_double(&expr).to_le_bytes().to_vec(),
+                _ => {
+                    let mut label = None;
+                    let value = eval2(&expr, Some(&mut label));
+                    if let Some(label) = label {
+                        relocations.push(Relocation {
+                            offset,
+                            label,
+                            addend: value,
+                        });
+                        return;
+                    }
+                    value.to_le_bytes().to_vec()
+                }
+            };
+            data[offset..offset + size].copy_from_slice(&bytes[..size]);
+        }
+    }
+}
+
+/// 定数式を評価する
+fn eval(node: &Node) -> i64 {
+    eval2(node, None)
+}
+
+/// 定数式を評価する。`label` を渡すと、大域変数のアドレスに定数を足した式も評価でき、
+/// その変数の名前を `label` に入れて、足した定数を返す
+fn eval2(node: &Node, label: Option<&mut Option<String>>) -> i64 {
+    let lhs = || eval(node.lhs.as_ref().unwrap());
+    let rhs = || eval(node.rhs.as_ref().unwrap());
+    // 符号なしの演算は結果が変わるものだけ区別する
+    let is_unsigned = || {
+        node.lhs
+            .as_ref()
+            .unwrap()
+            .ty
+            .as_ref()
+            .unwrap()
+            .is_unsigned()
+    };
+
+    let is_flonum = || node.lhs.as_ref().unwrap().ty.as_ref().unwrap().is_flonum();
+    let lhs_double = || eval_double(node.lhs.as_ref().unwrap());
+    let rhs_double = || eval_double(node.rhs.as_ref().unwrap());
+
+    match node.kind {
+        NodeKind::Num(n) => n,
+        NodeKind::Add => eval2(node.lhs.as_ref().unwrap(), label).wrapping_add(rhs()),
+        NodeKind::Sub => eval2(node.lhs.as_ref().unwrap(), label).wrapping_sub(rhs()),
+        NodeKind::Multiply => lhs().wrapping_mul(rhs()),
+        NodeKind::Div if is_unsigned() => ((lhs() as u64) / (rhs() as u64)) as i64,
+        NodeKind::Div => lhs().wrapping_div(rhs()),
+        NodeKind::Mod if is_unsigned() => ((lhs() as u64) % (rhs() as u64)) as i64,
+        NodeKind::Mod => lhs().wrapping_rem(rhs()),
+        NodeKind::Neg => lhs().wrapping_neg(),
+        NodeKind::BitAnd => lhs() & rhs(),
+        NodeKind::BitOr => lhs() | rhs(),
+        NodeKind::BitXor => lhs() ^ rhs(),
+        NodeKind::ShiftLeft => lhs().wrapping_shl(rhs() as u32),
+        NodeKind::ShiftRight if is_unsigned() => (lhs() as u64).wrapping_shr(rhs() as u32) as i64,
+        NodeKind::ShiftRight => lhs().wrapping_shr(rhs() as u32),
+        NodeKind::Equal if is_flonum() => (lhs_double() == rhs_double()) as i64,
+        NodeKind::Equal => (lhs() == rhs()) as i64,
+        NodeKind::NotEqual if is_flonum() => (lhs_double() != rhs_double()) as i64,
+        NodeKind::NotEqual => (lhs() != rhs()) as i64,
+        NodeKind::LessThan if is_flonum() => (lhs_double() < rhs_double()) as i64,
+        NodeKind::LessThan if is_unsigned() => ((lhs() as u64) < (rhs() as u64)) as i64,
+        NodeKind::LessThan => (lhs() < rhs()) as i64,
+        NodeKind::LessThanOrEqual if is_flonum() => (lhs_double() <= rhs_double()) as i64,
+        NodeKind::LessThanOrEqual if is_unsigned() => ((lhs() as u64) <= (rhs() as u64)) as i64,
+        NodeKind::LessThanOrEqual => (lhs() <= rhs()) as i64,
+        NodeKind::Cast => {
+            let value = match node.ty.as_ref().unwrap() {
+                Type::ULong if is_flonum() => lhs_double() as u64 as i64,
+                _ if is_flonum() => lhs_double() as i64,
+                _ => eval2(node.lhs.as_ref().unwrap(), label),
+            };
+            match node.ty.as_ref().unwrap() {
+                Type::Char => value as i8 as i64,
+                Type::UChar => value as u8 as i64,
+                Type::Short => value as i16 as i64,
+                Type::UShort => value as u16 as i64,
+                Type::Int | Type::Enum => value as i32 as i64,
+                Type::UInt => value as u32 as i64,
+                _ => value,
+            }
+        }
+        NodeKind::Not => (lhs() == 0) as i64,
+        NodeKind::BitNot => !lhs(),
+        NodeKind::LogicalAnd => (lhs() != 0 && rhs() != 0) as i64,
+        NodeKind::LogicalOr => (lhs() != 0 || rhs() != 0) as i64,
+        NodeKind::Comma => eval2(node.rhs.as_ref().unwrap(), label),
+        NodeKind::Conditional(ref condition, ref then, ref els) => {
+            if eval(condition) != 0 {
+                eval2(then, label)
+            } else {
+                eval2(els, label)
+            }
+        }
+        NodeKind::Addr => eval_rval(node.lhs.as_ref().unwrap(), label),
+        // 配列や関数はアドレスに変換される
+        NodeKind::Member(ref member) if matches!(member.ty, Type::Array(..)) => {
+            eval_rval(node.lhs.as_ref().unwrap(), label) + member.offset as i64
+        }
+        NodeKind::Var(ref var) if matches!(var.ty, Type::Array(..) | Type::Func { .. }) => {
+            eval_rval(node, label)
+        }
+        _ => panic!("定数式ではありません: {node:?}"),
+    }
+}
+
+/// 大域変数の中にある値のアドレスを、変数の名前とそこからのオフセットとして評価する
+fn eval_rval(node: &Node, label: Option<&mut Option<String>>) -> i64 {
+    match node.kind {
+        NodeKind::Var(ref var) if !var.is_local => match label {
+            Some(label) => {
+                *label = Some(var.name.clone());
+                0
+            }
+            None => panic!("定数式ではありません: {node:?}"),
+        },
+        NodeKind::Deref => eval2(node.lhs.as_ref().unwrap(), label),
+        NodeKind::Member(ref member) => {
+            eval_rval(node.lhs.as_ref().unwrap(), label) + member.offset as i64
+        }
+        _ => panic!("定数式ではありません: {node:?}"),
+    }
+}
+
+/// 浮動小数点数の定数式を評価する
+fn eval_double(node: &Node) -> f64 {
+    let lhs = || eval_double(node.lhs.as_ref().unwrap());
+    let rhs = || eval_double(node.rhs.as_ref().unwrap());
+
+    match node.kind {
+        NodeKind::FNum(value) => value,
+        NodeKind::Add => lhs() + rhs(),
+        NodeKind::Sub => lhs() - rhs(),
+        NodeKind::Multiply => lhs() * rhs(),
+        NodeKind::Div => lhs() / rhs(),
+        NodeKind::Neg => -lhs(),
+        NodeKind::Cast => {
+            let from = node.lhs.as_ref().unwrap();
+            let value = match from.ty.as_ref().unwrap() {
+                ty if ty.is_flonum() => lhs(),
+                Type::ULong => eval(from) as u64 as f64,
+                _ => eval(from) as f64,
+            };
+            match node.ty.as_ref().unwrap() {
+                Type::Float => value as f32 as f64,
+                _ => value,
+            }
+        }
+        NodeKind::Comma => rhs(),
+        NodeKind::Conditional(ref condition, ref then, ref els) => {
+            if eval(condition) != 0 {
+                eval_double(then)
+            } else {
+                eval_double(els)
+            }
+        }
+        _ => panic!("定数式ではありません: {node:?}"),
+    }
+}
+
+// C では `+` がポインタ演算としても使われるため、
+// ポインタに整数を足す場合は整数の値を指す先の型のサイズ倍する
+fn new_add(mut lhs: Node, mut rhs: Node) -> Node {
+    add_type(&mut lhs);
+    add_type(&mut rhs);
+
+    let lhs_ty = lhs.ty.clone().unwrap();
+    let rhs_ty = rhs.ty.clone().unwrap();
+
+    // num + num
+    if lhs_ty.is_numeric() && rhs_ty.is_numeric() {
+        return Node::new_binary(NodeKind::Add, lhs, rhs);
+    }
+
+    // num + ptr を ptr + num に入れ替える
+    if lhs_ty.is_integer() && rhs_ty.base().is_some() {
+        return new_add(rhs, lhs);
+    }
+
+    // ptr + num
+    if let (Some(base), true) = (lhs_ty.base(), rhs_ty.is_integer()) {
+        let rhs = Node::new_binary(NodeKind::Multiply, rhs, Node::new_num(base.size().into()));
+        return Node::new_binary(NodeKind::Add, lhs, rhs);
+    }
+
+    panic!("無効なオペランドです: {lhs_ty:?} + {rhs_ty:?}");
+}
+
+// `-` も `+` と同様にポインタ演算として使われる
+fn new_sub(mut lhs: Node, mut rhs: Node) -> Node {
+    add_type(&mut lhs);
+    add_type(&mut rhs);
+
+    let lhs_ty = lhs.ty.clone().unwrap();
+    let rhs_ty = rhs.ty.clone().unwrap();
+
+    // num - num
+    if lhs_ty.is_numeric() && rhs_ty.is_numeric() {
+        return Node::new_binary(NodeKind::Sub, lhs, rhs);
+    }
+
+    if let Some(base) = lhs_ty.base() {
+        // ptr - num
+        if rhs_ty.is_integer() {
+            let rhs = Node::new_binary(NodeKind::Multiply, rhs, Node::new_num(base.size().into()));
+            return Node::new_binary(NodeKind::Sub, lhs, rhs);
+        }
+
+        // ptr - ptr は二つのポインタの間にある要素の数を返す
+        if rhs_ty.base().is_some() {
+            let mut node = Node::new_binary(NodeKind::Sub, lhs, rhs);
+            node.ty = Some(Type::Long);
+            return Node::new_binary(NodeKind::Div, node, Node::new_num(base.size().into()));
+        }
+    }
+
+    panic!("無効なオペランドです: {lhs_ty:?} - {rhs_ty:?}");
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parser, token::Token, tokenizer::Tokenizer, types::Type};
 
     #[test]
     fn test_number() {
-        let (tree, _) = parser::Parser::new(vec![
+        let program = parser::Parser::new(vec![
+            Token::Int,
+            Token::Ident("main".to_string()),
+            Token::LeftParen,
+            Token::RightParen,
             Token::LeftBrace,
-            Token::Num(42),
+            Token::Num(42, Type::Int),
             Token::Semicolon,
             Token::RightBrace,
             Token::Eof,
         ])
         .parse();
         assert_eq!(
-            format!("{tree:?}",),
-            "Node { kind: Block([Node { kind: ExpressionStatement, lhs: Some(Node { kind: Num(42), lhs: None, rhs: None }), rhs: None }]), lhs: None, rhs: None }"
+            format!("{:?}", program.functions[0].body),
+            "Node { kind: Block([Node { kind: ExpressionStatement, lhs: Some(Node { kind: Num(42), lhs: None, rhs: None, ty: Some(Int) }), rhs: None, ty: None }]), lhs: None, rhs: None, ty: None }"
         );
     }
+
+    #[test]
+    #[should_panic(expected = "未定義の変数です: y")]
+    fn test_undefined_variable() {
+        let tokens = Tokenizer::new("int main() { int x; y = 3; return x; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "無効なオペランドです")]
+    fn test_add_pointers() {
+        let tokens = Tokenizer::new("int main() { int x, *p; p = &x; return p + p; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "無効なオペランドです")]
+    fn test_sub_pointer_from_integer() {
+        let tokens = Tokenizer::new("int main() { int x, *p; p = &x; return 1 - p; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "浮動小数点数のオペランドには使えない演算子です: Mod")]
+    fn test_mod_assign_double() {
+        let tokens = Tokenizer::new("int main() { double x = 5; x %= 2; return x; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "浮動小数点数のオペランドには使えない演算子です: ShiftLeft")]
+    fn test_shift_float() {
+        let tokens = Tokenizer::new("int main() { float x = 1; return x << 1; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "共用体ではありません: T")]
+    fn test_struct_tag_as_union() {
+        let tokens =
+            Tokenizer::new("struct T { int a; }; int main() { union T x; return 0; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "構造体ではありません: T")]
+    fn test_union_tag_as_struct() {
+        let tokens =
+            Tokenizer::new("union T; struct T { int a; }; int main() { return 0; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "ループか switch 文の外で break は使えません")]
+    fn test_break_outside_loop() {
+        let tokens = Tokenizer::new("int main() { if (1) break; return 0; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "ループの外で continue は使えません")]
+    fn test_continue_in_switch() {
+        let tokens = Tokenizer::new("int main() { switch (1) { case 1: continue; } }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "case の値が重複しています: 1")]
+    fn test_duplicate_case_after_conversion() {
+        // int に変換すると 4294967297 は 1 になる
+        let tokens =
+            Tokenizer::new("int main() { switch (1) { case 1: case 4294967297: return 0; } }")
+                .tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "未定義のラベルです: b")]
+    fn test_undefined_label() {
+        let tokens =
+            Tokenizer::new("int main() { a: goto b; } int f() { b: return 0; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "void ポインタを参照外ししています")]
+    fn test_deref_void_pointer() {
+        let tokens = Tokenizer::new("int main() { void *p; return *p; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "可変長引数の関数の外で va_start が使われています")]
+    fn test_va_start_in_non_variadic_function() {
+        let tokens =
+            Tokenizer::new("int f(int n) { va_list ap; va_start(ap, n); return 0; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "初期化子の要素が多すぎます")]
+    fn test_excess_initializer() {
+        let tokens = Tokenizer::new("int main() { int a[2] = {1, 2, 3}; return 0; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "配列の長さが決まっていません: a")]
+    fn test_array_without_length() {
+        let tokens = Tokenizer::new("int main() { int a[]; return 0; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
+
+    #[test]
+    #[should_panic(expected = "定数式ではありません")]
+    fn test_non_constant_global_initializer() {
+        let tokens = Tokenizer::new("int x; int y = x; int main() { return y; }").tokenize();
+        parser::Parser::new(tokens).parse();
+    }
 }
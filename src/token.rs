@@ -1,11 +1,36 @@
+use crate::types::Type;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Plus,
     Minus,
     Star,
     Slash,
+    Percent,    // %
+    Ampersand,  // &
+    Pipe,       // |
+    Caret,      // ^
+    Tilde,      // ~
+    ShiftLeft,  // <<
+    ShiftRight, // >>
+    Not,        // !
+    LogicalAnd, // &&
+    LogicalOr,  // ||
+    Question,   // ?
 
-    Assign, // =
+    Assign,           // =
+    PlusAssign,       // +=
+    MinusAssign,      // -=
+    StarAssign,       // *=
+    SlashAssign,      // /=
+    PercentAssign,    // %=
+    AmpersandAssign,  // &=
+    PipeAssign,       // |=
+    CaretAssign,      // ^=
+    ShiftLeftAssign,  // <<=
+    ShiftRightAssign, // >>=
+    Increment,        // ++
+    Decrement,        // --
 
     Equal,              // ==
     NotEqual,           // !=
@@ -18,19 +43,50 @@ pub enum Token {
     LeftBrace,  // {
     RightBrace, // }
 
+    LeftBracket,  // [
+    RightBracket, // ]
+
     LeftAngleBracket,  // <
     RightAngleBracket, // >
 
     Semicolon,
+    Colon,
+    Comma,
+    Dot,
+    Arrow,    // ->
+    Ellipsis, // ...
 
-    Num(i32),
+    Num(i64, Type),  // value, type
+    FNum(f64, Type), // value, type
     Ident(String),
+    Str(Vec<u8>), // 終端の \0 を含まない
 
     Return,
     If,
     Else,
     While,
     For,
+    Do,
+    Switch,
+    Case,
+    Default,
+    Break,
+    Continue,
+    Goto,
+    Void,
+    Char,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    Signed,
+    Unsigned,
+    Struct,
+    Union,
+    Enum,
+    Typedef,
+    Sizeof,
 
     Eof,
 }
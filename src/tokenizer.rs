@@ -1,7 +1,6 @@
 use core::panic;
-use std::str::FromStr;
 
-use crate::token::Token;
+use crate::{token::Token, types::Type};
 
 pub struct Tokenizer {
     input: String,
@@ -25,44 +24,66 @@ impl Tokenizer {
                 continue;
             }
 
-            if self.peek(6).as_str() == "return"
-                && !&self.peek(7).chars().nth(6).unwrap().is_alphanumeric()
-            {
-                tokens.push(Token::Return);
-                self.cursor += 6;
+            // 行コメント
+            if self.peek(2).as_str() == "//" {
+                self.cursor += 2;
+                while !matches!(self.current(), Some('\n') | None) {
+                    self.cursor += 1;
+                }
                 continue;
             }
 
-            if self.peek(5).as_str() == "while"
-                && !&self.peek(6).chars().nth(5).unwrap().is_alphanumeric()
-            {
-                tokens.push(Token::While);
-                self.cursor += 5;
+            // ブロックコメント
+            if self.peek(2).as_str() == "/*" {
+                let start = self.cursor;
+                self.cursor += 2;
+                while self.peek(2).as_str() != "*/" {
+                    if self.current().is_none() {
+                        let (line, column) = self.location(start);
+                        panic!("コメントが閉じられていません: {line}行{column}列目");
+                    }
+                    self.cursor += 1;
+                }
+                self.cursor += 2;
                 continue;
             }
 
-            if self.peek(4).as_str() == "else"
-                && !&self.peek(5).chars().nth(4).unwrap().is_alphanumeric()
-            {
-                tokens.push(Token::Else);
-                self.cursor += 4;
+            if c == '"' {
+                let string = self.read_string_literal();
+                tokens.push(Token::Str(string));
                 continue;
             }
 
-            if self.peek(3).as_str() == "for"
-                && !&self.peek(4).chars().nth(3).unwrap().is_alphanumeric()
+            // `.5` のように `.` から始まる数値もある
+            if c.is_ascii_digit()
+                || (c == '.'
+                    && self
+                        .peek(2)
+                        .chars()
+                        .nth(1)
+                        .is_some_and(|c| c.is_ascii_digit()))
             {
-                tokens.push(Token::For);
-                self.cursor += 3;
+                tokens.push(self.read_number());
                 continue;
             }
 
-            if self.peek(2).as_str() == "if"
-                && !&self.peek(3).chars().nth(2).unwrap().is_alphanumeric()
-            {
-                tokens.push(Token::If);
-                self.cursor += 2;
-                continue;
+            match self.peek(3).as_str() {
+                "<<=" => {
+                    tokens.push(Token::ShiftLeftAssign);
+                    self.cursor += 3;
+                    continue;
+                }
+                ">>=" => {
+                    tokens.push(Token::ShiftRightAssign);
+                    self.cursor += 3;
+                    continue;
+                }
+                "..." => {
+                    tokens.push(Token::Ellipsis);
+                    self.cursor += 3;
+                    continue;
+                }
+                _ => {}
             }
 
             match self.peek(2).as_str() {
@@ -86,6 +107,81 @@ impl Tokenizer {
                     self.cursor += 2;
                     continue;
                 }
+                "&&" => {
+                    tokens.push(Token::LogicalAnd);
+                    self.cursor += 2;
+                    continue;
+                }
+                "||" => {
+                    tokens.push(Token::LogicalOr);
+                    self.cursor += 2;
+                    continue;
+                }
+                "->" => {
+                    tokens.push(Token::Arrow);
+                    self.cursor += 2;
+                    continue;
+                }
+                "+=" => {
+                    tokens.push(Token::PlusAssign);
+                    self.cursor += 2;
+                    continue;
+                }
+                "-=" => {
+                    tokens.push(Token::MinusAssign);
+                    self.cursor += 2;
+                    continue;
+                }
+                "*=" => {
+                    tokens.push(Token::StarAssign);
+                    self.cursor += 2;
+                    continue;
+                }
+                "/=" => {
+                    tokens.push(Token::SlashAssign);
+                    self.cursor += 2;
+                    continue;
+                }
+                "%=" => {
+                    tokens.push(Token::PercentAssign);
+                    self.cursor += 2;
+                    continue;
+                }
+                "&=" => {
+                    tokens.push(Token::AmpersandAssign);
+                    self.cursor += 2;
+                    continue;
+                }
+                "|=" => {
+                    tokens.push(Token::PipeAssign);
+                    self.cursor += 2;
+                    continue;
+                }
+                "^=" => {
+                    tokens.push(Token::CaretAssign);
+                    self.cursor += 2;
+                    continue;
+                }
+                "++" => {
+                    tokens.push(Token::Increment);
+                    self.cursor += 2;
+                    continue;
+                }
+                "--" => {
+                    tokens.push(Token::Decrement);
+                    self.cursor += 2;
+                    continue;
+                }
+                "<<" => {
+                    tokens.push(Token::ShiftLeft);
+                    self.cursor += 2;
+                    continue;
+                }
+                ">>" => {
+                    tokens.push(Token::ShiftRight);
+                    self.cursor += 2;
+                    continue;
+                }
                 _ => {}
             }
 
@@ -110,6 +206,41 @@ impl Tokenizer {
                     self.cursor += 1;
                     continue;
                 }
+                "%" => {
+                    tokens.push(Token::Percent);
+                    self.cursor += 1;
+                    continue;
+                }
+                "&" => {
+                    tokens.push(Token::Ampersand);
+                    self.cursor += 1;
+                    continue;
+                }
+                "|" => {
+                    tokens.push(Token::Pipe);
+                    self.cursor += 1;
+                    continue;
+                }
+                "^" => {
+                    tokens.push(Token::Caret);
+                    self.cursor += 1;
+                    continue;
+                }
+                "~" => {
+                    tokens.push(Token::Tilde);
+                    self.cursor += 1;
+                    continue;
+                }
+                "!" => {
+                    tokens.push(Token::Not);
+                    self.cursor += 1;
+                    continue;
+                }
+                "?" => {
+                    tokens.push(Token::Question);
+                    self.cursor += 1;
+                    continue;
+                }
                 "(" => {
                     tokens.push(Token::LeftParen);
                     self.cursor += 1;
@@ -130,6 +261,16 @@ impl Tokenizer {
                     self.cursor += 1;
                     continue;
                 }
+                "[" => {
+                    tokens.push(Token::LeftBracket);
+                    self.cursor += 1;
+                    continue;
+                }
+                "]" => {
+                    tokens.push(Token::RightBracket);
+                    self.cursor += 1;
+                    continue;
+                }
                 "<" => {
                     tokens.push(Token::LeftAngleBracket);
                     self.cursor += 1;
@@ -145,6 +286,21 @@ impl Tokenizer {
                     self.cursor += 1;
                     continue;
                 }
+                ":" => {
+                    tokens.push(Token::Colon);
+                    self.cursor += 1;
+                    continue;
+                }
+                "," => {
+                    tokens.push(Token::Comma);
+                    self.cursor += 1;
+                    continue;
+                }
+                "." => {
+                    tokens.push(Token::Dot);
+                    self.cursor += 1;
+                    continue;
+                }
                 "=" => {
                     tokens.push(Token::Assign);
                     self.cursor += 1;
@@ -153,12 +309,8 @@ impl Tokenizer {
                 _ => {}
             }
 
-            if c.is_ascii_digit() {
-                let string: String = self.input.chars().skip(self.cursor).collect();
-                let (n, len) = str_to_fromstr::<i32>(&string).unwrap();
-
-                tokens.push(Token::Num(n));
-                self.cursor += len;
+            if c == '\'' {
+                tokens.push(self.read_char_literal());
                 continue;
             }
 
@@ -178,7 +330,7 @@ impl Tokenizer {
                     .collect::<String>();
 
                 self.cursor += name.len();
-                tokens.push(Token::Ident(name));
+                tokens.push(keyword(&name).unwrap_or(Token::Ident(name)));
 
                 continue;
             }
@@ -197,22 +349,292 @@ impl Tokenizer {
     fn peek(&self, n: usize) -> String {
         self.input.chars().skip(self.cursor).take(n).collect()
     }
+
+    fn current(&self) -> Option<char> {
+        self.input.chars().nth(self.cursor)
+    }
+
+    /// 位置 `pos` の行番号と列番号を返す（共に1始まり）
+    fn location(&self, pos: usize) -> (usize, usize) {
+        let before = self.input.chars().take(pos).collect::<String>();
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+
+        (line, column)
+    }
+
+    fn read_string_literal(&mut self) -> Vec<u8> {
+        let start = self.cursor;
+        self.cursor += 1;
+
+        let mut bytes = vec![];
+        loop {
+            match self.current() {
+                Some('"') => break,
+                Some('\\') => {
+                    self.cursor += 1;
+                    bytes.push(self.read_escaped_char());
+                }
+                Some('\n') | None => panic!(
+                    "文字列リテラルが閉じられていません: {}",
+                    self.input.chars().skip(start).collect::<String>()
+                ),
+                Some(c) => {
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                    self.cursor += 1;
+                }
+            }
+        }
+        self.cursor += 1;
+
+        bytes
+    }
+
+    fn read_number(&mut self) -> Token {
+        // 数値リテラルになり得る文字の並び (pp-number) をまとめて切り出してから解釈する
+        let start = self.cursor;
+        loop {
+            let next = self.peek(2);
+            let mut chars = next.chars();
+            match (chars.next(), chars.next()) {
+                (Some('e' | 'E' | 'p' | 'P'), Some('+' | '-')) => self.cursor += 2,
+                (Some(c), _) if c.is_ascii_alphanumeric() || c == '.' => self.cursor += 1,
+                _ => break,
+            }
+        }
+        let text: String = self
+            .input
+            .chars()
+            .skip(start)
+            .take(self.cursor - start)
+            .collect();
+
+        let lower = text.to_ascii_lowercase();
+        let is_hex = lower.starts_with("0x");
+        if lower.contains('.')
+            || (!is_hex && lower.contains('e'))
+            || (is_hex && lower.contains('p'))
+        {
+            float_literal(&text)
+        } else {
+            int_literal(&text)
+        }
+    }
+
+    fn read_char_literal(&mut self) -> Token {
+        let start = self.cursor;
+        self.cursor += 1;
+
+        let c = match self.current() {
+            Some('\'') | Some('\n') | None => panic!(
+                "文字リテラルが不正です: {}",
+                self.input.chars().skip(start).collect::<String>()
+            ),
+            Some('\\') => {
+                self.cursor += 1;
+                self.read_escaped_char()
+            }
+            Some(c) => {
+                self.cursor += 1;
+                c as u8
+            }
+        };
+
+        if self.current() != Some('\'') {
+            panic!(
+                "文字リテラルが閉じられていません: {}",
+                self.input.chars().skip(start).collect::<String>()
+            );
+        }
+        self.cursor += 1;
+
+        // 文字定数は int 型だが、値は char として符号拡張する
+        Token::Num(c as i8 as i64, Type::Int)
+    }
+
+    // `\` の直後から読み始める
+    fn read_escaped_char(&mut self) -> u8 {
+        let Some(c) = self.current() else {
+            panic!("エスケープシーケンスが不正です");
+        };
+
+        // 8進数のエスケープシーケンスは最大3桁
+        if c.is_digit(8) {
+            let mut value = 0;
+            for _ in 0..3 {
+                match self.current().and_then(|c| c.to_digit(8)) {
+                    Some(digit) => {
+                        value = value * 8 + digit;
+                        self.cursor += 1;
+                    }
+                    None => break,
+                }
+            }
+            return value as u8;
+        }
+
+        // 16進数のエスケープシーケンスは桁数に制限がない
+        if c == 'x' {
+            self.cursor += 1;
+            if !self.current().is_some_and(|c| c.is_ascii_hexdigit()) {
+                panic!("16進数のエスケープシーケンスが不正です");
+            }
+
+            let mut value: u32 = 0;
+            while let Some(digit) = self.current().and_then(|c| c.to_digit(16)) {
+                value = value.wrapping_mul(16).wrapping_add(digit);
+                self.cursor += 1;
+            }
+            return value as u8;
+        }
+
+        self.cursor += 1;
+        match c {
+            'a' => 7,
+            'b' => 8,
+            't' => 9,
+            'n' => 10,
+            'v' => 11,
+            'f' => 12,
+            'r' => 13,
+            // [GNU] \e は ASCII のエスケープ文字
+            'e' => 27,
+            _ => c as u8,
+        }
+    }
 }
 
-fn str_to_fromstr<F: FromStr>(str: &str) -> Result<(F, usize), F::Err> {
-    let index = str
-        .chars()
-        .position(|byte| !byte.is_ascii_digit())
-        .unwrap_or(str.len());
+fn int_literal(text: &str) -> Token {
+    let lower = text.to_ascii_lowercase();
+    let (radix, rest) = match lower.as_bytes() {
+        [b'0', b'x', c, ..] if c.is_ascii_hexdigit() => (16, &text[2..]),
+        [b'0', b'b', b'0' | b'1', ..] => (2, &text[2..]),
+        [b'0', ..] => (8, text),
+        _ => (10, text),
+    };
+
+    let len = rest.chars().take_while(|c| c.is_digit(radix)).count();
+    let (digits, suffix) = rest.split_at(len);
+
+    let Ok(value) = u64::from_str_radix(digits, radix) else {
+        panic!("整数リテラルが大きすぎます: {text}");
+    };
+
+    // 接尾辞 u と l (ll) はどちらの順でも、大文字と小文字のどちらでも書ける
+    let (is_long, is_unsigned) = match suffix {
+        "" => (false, false),
+        "u" | "U" => (false, true),
+        "l" | "L" | "ll" | "LL" => (true, false),
+        "lu" | "lU" | "Lu" | "LU" | "ul" | "uL" | "Ul" | "UL" | "llu" | "llU" | "LLu" | "LLU"
+        | "ull" | "uLL" | "Ull" | "ULL" => (true, true),
+        _ => panic!("無効な数値リテラルです: {text}"),
+    };
+
+    // 値が収まる最初の型を選ぶ。10進数のリテラルは符号なしの型にならないが、
+    // long に収まらない値は unsigned long にする
+    let ty = if radix == 10 {
+        match (is_long, is_unsigned) {
+            (true, true) => Type::ULong,
+            (_, false) if value >> 63 != 0 => Type::ULong,
+            (true, false) => Type::Long,
+            (false, true) if value >> 32 != 0 => Type::ULong,
+            (false, true) => Type::UInt,
+            (false, false) if value >> 31 != 0 => Type::Long,
+            (false, false) => Type::Int,
+        }
+    } else {
+        match (is_long, is_unsigned) {
+            (true, true) => Type::ULong,
+            (true, false) if value >> 63 != 0 => Type::ULong,
+            (true, false) => Type::Long,
+            (false, true) if value >> 32 != 0 => Type::ULong,
+            (false, true) => Type::UInt,
+            (false, false) if value >> 63 != 0 => Type::ULong,
+            (false, false) if value >> 32 != 0 => Type::Long,
+            (false, false) if value >> 31 != 0 => Type::UInt,
+            (false, false) => Type::Int,
+        }
+    };
 
-    let (digit_part, _) = str.split_at(index);
+    Token::Num(value as i64, ty)
+}
 
-    digit_part.parse().map(|value| (value, digit_part.len()))
+// 接尾辞 f は float、接尾辞なしは double。long double は double として扱う
+fn float_literal(text: &str) -> Token {
+    let (digits, ty) = match text.char_indices().last() {
+        Some((i, 'f' | 'F')) => (&text[..i], Type::Float),
+        Some((i, 'l' | 'L')) => (&text[..i], Type::Double),
+        _ => (text, Type::Double),
+    };
+
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => hex_float(hex),
+        None => digits.parse::<f64>().ok(),
+    };
+
+    match value {
+        Some(value) => Token::FNum(value, ty),
+        None => panic!("無効な数値リテラルです: {text}"),
+    }
+}
+
+// 16進の浮動小数点数（0x1.8p3 など）。指数は2の累乗で、省略できない
+fn hex_float(text: &str) -> Option<f64> {
+    let (mantissa, exponent) = text.split_once(['p', 'P'])?;
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+
+    let mut value = 0.0;
+    for c in int_part.chars().chain(frac_part.chars()) {
+        value = value * 16.0 + c.to_digit(16)? as f64;
+    }
+    let exponent = exponent.parse::<i32>().ok()? - 4 * frac_part.len() as i32;
+    Some(value * 2f64.powi(exponent))
+}
+
+fn keyword(name: &str) -> Option<Token> {
+    let token = match name {
+        "return" => Token::Return,
+        "if" => Token::If,
+        "else" => Token::Else,
+        "while" => Token::While,
+        "for" => Token::For,
+        "do" => Token::Do,
+        "switch" => Token::Switch,
+        "case" => Token::Case,
+        "default" => Token::Default,
+        "break" => Token::Break,
+        "continue" => Token::Continue,
+        "goto" => Token::Goto,
+        "void" => Token::Void,
+        "char" => Token::Char,
+        "short" => Token::Short,
+        "int" => Token::Int,
+        "long" => Token::Long,
+        "float" => Token::Float,
+        "double" => Token::Double,
+        "signed" => Token::Signed,
+        "unsigned" => Token::Unsigned,
+        "struct" => Token::Struct,
+        "union" => Token::Union,
+        "enum" => Token::Enum,
+        "typedef" => Token::Typedef,
+        "sizeof" => Token::Sizeof,
+        _ => return None,
+    };
+
+    Some(token)
 }
 
 #[cfg(test)]
 mod test {
-    use crate::token::Token;
+    use crate::{token::Token, types::Type};
 
     use super::Tokenizer;
 
@@ -223,7 +645,12 @@ mod test {
             format!("{:?}", tokens),
             format!(
                 "{:?}",
-                vec![Token::Num(5), Token::Num(5), Token::Num(5), Token::Eof]
+                vec![
+                    Token::Num(5, Type::Int),
+                    Token::Num(5, Type::Int),
+                    Token::Num(5, Type::Int),
+                    Token::Eof
+                ]
             )
         );
     }
@@ -251,6 +678,22 @@ mod test {
             format!("{:?}", vec![Token::Star, Token::Eof])
         );
 
+        // Percent, Pipe, Caret, Tilde
+        let tokens = Tokenizer::new("%|^~").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::Percent,
+                    Token::Pipe,
+                    Token::Caret,
+                    Token::Tilde,
+                    Token::Eof
+                ]
+            )
+        );
+
         // LeftParen, RightParen
         let tokens = Tokenizer::new("()").tokenize();
         assert_eq!(
@@ -301,6 +744,78 @@ mod test {
             format!("{:?}", tokens),
             format!("{:?}", vec![Token::NotEqual, Token::Eof])
         );
+
+        let tokens = Tokenizer::new("<< >>").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![Token::ShiftLeft, Token::ShiftRight, Token::Eof]
+            )
+        );
+    }
+
+    #[test]
+    fn test_assignment_tokens() {
+        let tokens = Tokenizer::new("+= -= *= /= %= &= |= ^= <<= >>= ++ --").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::PlusAssign,
+                    Token::MinusAssign,
+                    Token::StarAssign,
+                    Token::SlashAssign,
+                    Token::PercentAssign,
+                    Token::AmpersandAssign,
+                    Token::PipeAssign,
+                    Token::CaretAssign,
+                    Token::ShiftLeftAssign,
+                    Token::ShiftRightAssign,
+                    Token::Increment,
+                    Token::Decrement,
+                    Token::Eof
+                ]
+            )
+        );
+
+        // 3文字の演算子は2文字の演算子より先に切り出す
+        let tokens = Tokenizer::new("a<<=b<<c").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::Ident("a".to_string()),
+                    Token::ShiftLeftAssign,
+                    Token::Ident("b".to_string()),
+                    Token::ShiftLeft,
+                    Token::Ident("c".to_string()),
+                    Token::Eof
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        let tokens = Tokenizer::new("(int n, ...)").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::LeftParen,
+                    Token::Int,
+                    Token::Ident("n".to_string()),
+                    Token::Comma,
+                    Token::Ellipsis,
+                    Token::RightParen,
+                    Token::Eof
+                ]
+            )
+        );
     }
 
     #[test]
@@ -311,20 +826,176 @@ mod test {
             format!(
                 "{:?}",
                 vec![
-                    Token::Num(1),
+                    Token::Num(1, Type::Int),
                     Token::Plus,
-                    Token::Num(5),
+                    Token::Num(5, Type::Int),
                     Token::Minus,
                     Token::LeftParen,
-                    Token::Num(20),
+                    Token::Num(20, Type::Int),
                     Token::Star,
-                    Token::Num(2),
+                    Token::Num(2, Type::Int),
                     Token::RightParen,
                     Token::Equal,
-                    Token::Num(10),
+                    Token::Num(10, Type::Int),
                     Token::Eof,
                 ]
             )
         )
     }
+
+    #[test]
+    fn test_keywords() {
+        let tokens = Tokenizer::new("int returnx if_x return sizeof").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::Int,
+                    Token::Ident("returnx".to_string()),
+                    Token::Ident("if_x".to_string()),
+                    Token::Return,
+                    Token::Sizeof,
+                    Token::Eof,
+                ]
+            )
+        );
+
+        let tokens = Tokenizer::new("unsigned long long short signed").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::Unsigned,
+                    Token::Long,
+                    Token::Long,
+                    Token::Short,
+                    Token::Signed,
+                    Token::Eof,
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_string_literal() {
+        let tokens = Tokenizer::new(r#""abc" "\n\t\\\"" "\x41\101\0" "あ""#).tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::Str(b"abc".to_vec()),
+                    Token::Str(b"\n\t\\\"".to_vec()),
+                    Token::Str(b"AA\0".to_vec()),
+                    Token::Str("あ".as_bytes().to_vec()),
+                    Token::Eof,
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let tokens = Tokenizer::new("1 // 2 3\n4 /* 5\n6 */ 7 // 8").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::Num(1, Type::Int),
+                    Token::Num(4, Type::Int),
+                    Token::Num(7, Type::Int),
+                    Token::Eof
+                ]
+            )
+        );
+
+        let tokens = Tokenizer::new("/**/1/*/ */").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!("{:?}", vec![Token::Num(1, Type::Int), Token::Eof])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "コメントが閉じられていません: 2行3列目")]
+    fn test_unterminated_block_comment() {
+        Tokenizer::new("1\n2 /* 3\n4").tokenize();
+    }
+
+    #[test]
+    fn test_int_literals() {
+        let tokens =
+            Tokenizer::new("0x1F 017 0b101 10 2147483648 0xffffffff 1u 1L 1llu 9223372036854775807 9223372036854775808 18446744073709551615L").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::Num(31, Type::Int),
+                    Token::Num(15, Type::Int),
+                    Token::Num(5, Type::Int),
+                    Token::Num(10, Type::Int),
+                    Token::Num(2147483648, Type::Long),
+                    Token::Num(4294967295, Type::UInt),
+                    Token::Num(1, Type::UInt),
+                    Token::Num(1, Type::Long),
+                    Token::Num(1, Type::ULong),
+                    Token::Num(9223372036854775807, Type::Long),
+                    Token::Num(-9223372036854775808, Type::ULong),
+                    Token::Num(-1, Type::ULong),
+                    Token::Eof,
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_float_literals() {
+        let tokens = Tokenizer::new("1.5 .5 3. 1e3 2.5e-1 0x1p4 1.5f 2.0L 0x1.8p1").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::FNum(1.5, Type::Double),
+                    Token::FNum(0.5, Type::Double),
+                    Token::FNum(3.0, Type::Double),
+                    Token::FNum(1000.0, Type::Double),
+                    Token::FNum(0.25, Type::Double),
+                    Token::FNum(16.0, Type::Double),
+                    Token::FNum(1.5, Type::Float),
+                    Token::FNum(2.0, Type::Double),
+                    Token::FNum(3.0, Type::Double),
+                    Token::Eof,
+                ]
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "無効な数値リテラルです")]
+    fn test_invalid_int_literal() {
+        Tokenizer::new("09").tokenize();
+    }
+
+    #[test]
+    fn test_char_literals() {
+        let tokens = Tokenizer::new(r"'a' '\n' '\0' '\x80'").tokenize();
+        assert_eq!(
+            format!("{:?}", tokens),
+            format!(
+                "{:?}",
+                vec![
+                    Token::Num(97, Type::Int),
+                    Token::Num(10, Type::Int),
+                    Token::Num(0, Type::Int),
+                    Token::Num(-128, Type::Int),
+                    Token::Eof,
+                ]
+            )
+        );
+    }
 }
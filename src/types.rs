@@ -0,0 +1,423 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::node::{Node, NodeKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Void,
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Long, // long と long long は同じ 8 バイトの型として扱う
+    ULong,
+    Float,
+    Double,
+    Enum,
+    Ptr(Box<Type>),        // base
+    Array(Box<Type>, i32), // base, length
+    Func {
+        return_ty: Box<Type>,
+        params: Vec<(String, Type)>, // name, type
+        is_variadic: bool,
+    },
+    // 前方宣言された構造体を後から完成させられるように、同じタグの型で中身を共有する
+    Struct(Rc<RefCell<StructType>>),
+    Union(Rc<RefCell<StructType>>),
+}
+
+impl Type {
+    pub fn pointer_to(base: Type) -> Self {
+        Type::Ptr(Box::new(base))
+    }
+
+    pub fn array_of(base: Type, len: i32) -> Self {
+        Type::Array(Box::new(base), len)
+    }
+
+    pub fn size(&self) -> i32 {
+        match self {
+            // [GNU] void* のポインタ演算のため、void のサイズは 1 とする
+            Type::Void | Type::Char | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt | Type::Float | Type::Enum => 4,
+            Type::Long | Type::ULong | Type::Double | Type::Ptr(_) => 8,
+            Type::Array(base, len) => base.size() * len,
+            Type::Func { .. } => 1,
+            Type::Struct(st) | Type::Union(st) => {
+                let st = st.borrow();
+                if !st.is_complete {
+                    panic!("不完全な型です: {self:?}");
+                }
+                st.size
+            }
+        }
+    }
+
+    pub fn align(&self) -> i32 {
+        match self {
+            Type::Void | Type::Char | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt | Type::Float | Type::Enum => 4,
+            Type::Long | Type::ULong | Type::Double | Type::Ptr(_) => 8,
+            Type::Array(base, _) => base.align(),
+            Type::Func { .. } => 1,
+            Type::Struct(st) | Type::Union(st) => st.borrow().align,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Type::Char
+                | Type::UChar
+                | Type::Short
+                | Type::UShort
+                | Type::Int
+                | Type::UInt
+                | Type::Long
+                | Type::ULong
+                | Type::Enum
+        )
+    }
+
+    pub fn is_flonum(&self) -> bool {
+        matches!(self, Type::Float | Type::Double)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_flonum()
+    }
+
+    /// 符号なしの値として扱う型か（ポインタはアドレスとして符号なしで比較する）
+    pub fn is_unsigned(&self) -> bool {
+        matches!(
+            self,
+            Type::UChar | Type::UShort | Type::UInt | Type::ULong | Type::Ptr(_)
+        )
+    }
+
+    /// ポインタか配列であればその要素の型を返す
+    pub fn base(&self) -> Option<&Type> {
+        match self {
+            Type::Ptr(base) | Type::Array(base, _) => Some(base),
+            _ => None,
+        }
+    }
+}
+
+/// 構造体・共用体のメンバとレイアウト
+pub struct StructType {
+    pub members: Vec<Member>,
+    pub size: i32,
+    pub align: i32,
+    pub is_complete: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: String,
+    pub ty: Type,
+    pub offset: i32,
+}
+
+// 自己参照する構造体で無限に再帰しないよう、メンバの型は出力しない
+impl fmt::Debug for StructType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let members: Vec<&str> = self.members.iter().map(|m| m.name.as_str()).collect();
+        f.debug_struct("StructType")
+            .field("members", &members)
+            .field("size", &self.size)
+            .field("align", &self.align)
+            .finish()
+    }
+}
+
+// 構造体の型は宣言ごとに別の型なので、メンバではなく同じ宣言を指しているかで比較する。
+// 自己参照する構造体でもメンバをたどって無限に再帰することがない
+impl PartialEq for StructType {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl StructType {
+    pub fn new_incomplete() -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(StructType {
+            members: vec![],
+            size: -1,
+            align: 1,
+            is_complete: false,
+        }))
+    }
+
+    /// メンバを構造体として配置する
+    pub fn new_struct(members: Vec<(String, Type)>) -> Self {
+        let mut offset = 0;
+        let mut align = 1;
+
+        let members = members
+            .into_iter()
+            .map(|(name, ty)| {
+                offset = align_to(offset, ty.align());
+                let member = Member { name, offset, ty };
+                offset += member.ty.size();
+                align = align.max(member.ty.align());
+                member
+            })
+            .collect();
+
+        StructType {
+            members,
+            // 配列にしたときに次の要素が揃うよう、末尾にパディングを入れる
+            size: align_to(offset, align),
+            align,
+            is_complete: true,
+        }
+    }
+
+    /// メンバを共用体として配置する（全てのメンバのオフセットは 0）
+    pub fn new_union(members: Vec<(String, Type)>) -> Self {
+        let mut size = 0;
+        let mut align = 1;
+
+        let members = members
+            .into_iter()
+            .map(|(name, ty)| {
+                size = size.max(ty.size());
+                align = align.max(ty.align());
+                Member {
+                    name,
+                    ty,
+                    offset: 0,
+                }
+            })
+            .collect();
+
+        StructType {
+            members,
+            size: align_to(size, align),
+            align,
+            is_complete: true,
+        }
+    }
+}
+
+/// 構造体・共用体のメンバを名前で探す
+pub fn get_member(ty: &Type, name: &str) -> Member {
+    let (Type::Struct(st) | Type::Union(st)) = ty else {
+        panic!("構造体でも共用体でもありません: {ty:?}");
+    };
+
+    match st.borrow().members.iter().find(|m| m.name == name) {
+        Some(member) => member.clone(),
+        None => panic!("そのようなメンバはありません: {name}"),
+    }
+}
+
+pub fn align_to(n: i32, align: i32) -> i32 {
+    (n + align - 1) / align * align
+}
+
+/// 二項演算のオペランドを揃える先の型を返す
+pub fn get_common_type(ty1: &Type, ty2: &Type) -> Type {
+    if let Some(base) = ty1.base() {
+        return Type::pointer_to(base.clone());
+    }
+
+    // 浮動小数点数が混ざっていれば、精度の高い方の浮動小数点数にそろえる
+    if let (Type::Double, _) | (_, Type::Double) = (ty1, ty2) {
+        return Type::Double;
+    }
+    if let (Type::Float, _) | (_, Type::Float) = (ty1, ty2) {
+        return Type::Float;
+    }
+
+    // 整数拡張: int より小さい型は int に変換する
+    let promote = |ty: &Type| {
+        if ty.size() < 4 {
+            Type::Int
+        } else {
+            ty.clone()
+        }
+    };
+    let ty1 = promote(ty1);
+    let ty2 = promote(ty2);
+
+    if ty1.size() != ty2.size() {
+        return if ty1.size() < ty2.size() { ty2 } else { ty1 };
+    }
+
+    if ty2.is_unsigned() {
+        ty2
+    } else {
+        ty1
+    }
+}
+
+/// 通常の算術変換: 両方のオペランドを共通の型に変換する
+fn usual_arith_conv(lhs: &mut Node, rhs: &mut Node) {
+    let ty = get_common_type(lhs.ty.as_ref().unwrap(), rhs.ty.as_ref().unwrap());
+    cast_in_place(lhs, ty.clone());
+    cast_in_place(rhs, ty);
+}
+
+fn cast_in_place(node: &mut Node, ty: Type) {
+    let expr = std::mem::replace(node, Node::new_num(0));
+    *node = Node::new_cast(expr, ty);
+}
+
+/// ノードとその子孫に型を付ける
+pub fn add_type(node: &mut Node) {
+    if node.ty.is_some() {
+        return;
+    }
+
+    if let Some(lhs) = node.lhs.as_mut() {
+        add_type(lhs);
+    }
+    if let Some(rhs) = node.rhs.as_mut() {
+        add_type(rhs);
+    }
+
+    match &mut node.kind {
+        NodeKind::If(condition, then, els) => {
+            add_type(condition);
+            add_type(then);
+            if let Some(els) = els.as_mut() {
+                add_type(els);
+            }
+        }
+        NodeKind::While(condition, body) => {
+            add_type(condition);
+            add_type(body);
+        }
+        NodeKind::For(init, condition, increment, body) => {
+            for node in [init.as_mut(), condition.as_mut(), increment.as_mut()]
+                .into_iter()
+                .flatten()
+            {
+                add_type(node);
+            }
+            add_type(body);
+        }
+        NodeKind::Conditional(condition, then, els) => {
+            add_type(condition);
+            add_type(then);
+            add_type(els);
+        }
+        NodeKind::Do(body, condition)
+        | NodeKind::Switch {
+            condition, body, ..
+        } => {
+            add_type(body);
+            add_type(condition);
+        }
+        NodeKind::Case(_, statement)
+        | NodeKind::Default(statement)
+        | NodeKind::Label(_, statement) => add_type(statement),
+        NodeKind::Block(nodes) | NodeKind::FunCall(_, nodes, _) => {
+            for node in nodes {
+                add_type(node);
+            }
+        }
+        _ => {}
+    }
+
+    // 整数にしか使えない演算子
+    if let NodeKind::Mod
+    | NodeKind::BitAnd
+    | NodeKind::BitOr
+    | NodeKind::BitXor
+    | NodeKind::BitNot
+    | NodeKind::ShiftLeft
+    | NodeKind::ShiftRight = node.kind
+    {
+        for operand in [node.lhs.as_ref(), node.rhs.as_ref()].into_iter().flatten() {
+            if operand.ty.as_ref().unwrap().is_flonum() {
+                panic!(
+                    "浮動小数点数のオペランドには使えない演算子です: {:?}",
+                    node.kind
+                );
+            }
+        }
+    }
+
+    // 暗黙の型変換
+    match &mut node.kind {
+        NodeKind::Add
+        | NodeKind::Sub
+        | NodeKind::Multiply
+        | NodeKind::Div
+        | NodeKind::Mod
+        | NodeKind::BitAnd
+        | NodeKind::BitOr
+        | NodeKind::BitXor
+        | NodeKind::Equal
+        | NodeKind::NotEqual
+        | NodeKind::LessThan
+        | NodeKind::LessThanOrEqual => usual_arith_conv(
+            node.lhs.as_deref_mut().unwrap(),
+            node.rhs.as_deref_mut().unwrap(),
+        ),
+        NodeKind::Neg | NodeKind::BitNot | NodeKind::ShiftLeft | NodeKind::ShiftRight => {
+            let lhs = node.lhs.as_deref_mut().unwrap();
+            let ty = get_common_type(&Type::Int, lhs.ty.as_ref().unwrap());
+            cast_in_place(lhs, ty);
+        }
+        NodeKind::Assign => {
+            let ty = node.lhs.as_ref().unwrap().ty.clone().unwrap();
+            match ty {
+                Type::Array(..) => panic!("配列には代入できません: {node:?}"),
+                Type::Struct(_) | Type::Union(_) => {}
+                _ => cast_in_place(node.rhs.as_deref_mut().unwrap(), ty),
+            }
+        }
+        NodeKind::Conditional(_, then, els)
+            if !matches!(then.ty, Some(Type::Struct(_) | Type::Union(_))) =>
+        {
+            usual_arith_conv(then, els)
+        }
+        _ => {}
+    }
+
+    node.ty = match &node.kind {
+        NodeKind::Add
+        | NodeKind::Sub
+        | NodeKind::Multiply
+        | NodeKind::Div
+        | NodeKind::Mod
+        | NodeKind::Neg
+        | NodeKind::BitAnd
+        | NodeKind::BitOr
+        | NodeKind::BitXor
+        | NodeKind::BitNot
+        | NodeKind::ShiftLeft
+        | NodeKind::ShiftRight
+        | NodeKind::Assign => node.lhs.as_ref().unwrap().ty.clone(),
+        NodeKind::Equal
+        | NodeKind::NotEqual
+        | NodeKind::LessThan
+        | NodeKind::LessThanOrEqual
+        | NodeKind::Not
+        | NodeKind::LogicalAnd
+        | NodeKind::LogicalOr
+        | NodeKind::Num(_) => Some(Type::Int),
+        NodeKind::FNum(_) => Some(Type::Double),
+        NodeKind::Comma => node.rhs.as_ref().unwrap().ty.clone(),
+        NodeKind::Conditional(_, then, _) => then.ty.clone(),
+        NodeKind::Var(var) => Some(var.ty.clone()),
+        NodeKind::Member(member) => Some(member.ty.clone()),
+        NodeKind::Addr => Some(Type::pointer_to(
+            node.lhs.as_ref().unwrap().ty.clone().unwrap(),
+        )),
+        NodeKind::Deref => match node.lhs.as_ref().unwrap().ty.as_ref().unwrap().base() {
+            Some(Type::Void) => panic!("void ポインタを参照外ししています: {node:?}"),
+            Some(base) => Some(base.clone()),
+            None => panic!("ポインタではない値を参照外ししています: {node:?}"),
+        },
+        _ => None,
+    };
+}